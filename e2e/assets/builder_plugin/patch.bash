@@ -0,0 +1 @@
+cat <<<"$(jq '.canisters.plugin={"type":"example","source":"main.wasm"}' dfx.json)" >dfx.json
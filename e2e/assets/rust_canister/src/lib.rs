@@ -0,0 +1,17 @@
+//! A canister without dependencies, which replies to `hello` with the Candid encoding of
+//! the text "hello".
+
+#[link(wasm_import_module = "ic0")]
+extern "C" {
+    fn msg_reply_data_append(src: u32, size: u32);
+    fn msg_reply();
+}
+
+#[export_name = "canister_query hello"]
+pub extern "C" fn hello() {
+    let reply = b"DIDL\x00\x01\x71\x05hello";
+    unsafe {
+        msg_reply_data_append(reply.as_ptr() as u32, reply.len() as u32);
+        msg_reply();
+    }
+}
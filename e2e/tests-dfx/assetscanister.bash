@@ -130,6 +130,102 @@ teardown() {
     diff encoded-compressed-2 src/e2e_project_assets/assets/notreally.js
 }
 
+@test "generates brotli content encodings as configured" {
+    install_asset assetscanister
+    for i in $(seq 1 400); do
+      echo "{\"item\": $i}," >>src/e2e_project_assets/assets/data.json
+      echo "some easily duplicate text $i" >>src/e2e_project_assets/assets/plain.txt
+    done
+    dd if=/dev/urandom of=src/e2e_project_assets/assets/random.js bs=10000 count=1
+    cat <<<"$(jq '.canisters.e2e_project_assets.compression=[{"match":"*.txt","encodings":[]},{"match":"/data.json","encodings":["br"],"level":5}]' dfx.json)" >dfx.json
+
+    dfx_start
+    assert_command dfx deploy
+    assert_match "/random.js \(gzip\) is not smaller than the original, skipping"
+    assert_match "The br encodings save [0-9]+ bytes."
+
+    assert_command dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/data.json";accept_encodings=vec{"br"}})'
+    assert_match 'content_encoding = "br"'
+    assert_command_fail dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/data.json";accept_encodings=vec{"gzip"}})'
+    assert_command_fail dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/plain.txt";accept_encodings=vec{"gzip"}})'
+    assert_command_fail dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/random.js";accept_encodings=vec{"gzip"}})'
+
+    cat <<<"$(jq '.canisters.e2e_project_assets.compression=[{"match":"*.json","encodings":["gzip"],"level":10}]' dfx.json)" >dfx.json
+    assert_command_fail dfx deploy
+    assert_match "The compression level of gzip for '\*.json' must be at most 9."
+}
+
+@test "applies the rules of .ic-assets.json files" {
+    install_asset assetscanister
+    mkdir -p src/e2e_project_assets/assets/drafts
+    echo "draft" >src/e2e_project_assets/assets/drafts/a.txt
+    for i in $(seq 1 400); do
+      echo "some easily duplicate text $i" >>src/e2e_project_assets/assets/data.bin
+    done
+    echo '[{"match":"drafts/**","ignore":true},{"match":"data.bin","content_type":"text/plain","encodings":["gzip"],"max_age":60}]' >src/e2e_project_assets/assets/.ic-assets.json
+
+    dfx_start
+    assert_command_fail dfx deploy
+    assert_match "unknown field \`max_age\`"
+
+    echo '[{"match":"drafts/**","ignore":true},{"match":"data.bin","content_type":"text/plain","encodings":["gzip"]}]' >src/e2e_project_assets/assets/.ic-assets.json
+    assert_command dfx deploy
+
+    assert_command dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/data.bin";accept_encodings=vec{"gzip"}})'
+    assert_match 'content_type = "text/plain"'
+    assert_match 'content_encoding = "gzip"'
+    assert_command_fail dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/data.bin";accept_encodings=vec{"br"}})'
+    assert_command_fail dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/drafts/a.txt";accept_encodings=vec{"identity"}})'
+    assert_command_fail dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/.ic-assets.json";accept_encodings=vec{"identity"}})'
+
+    echo '[{"match":"data.bin","content_type":"application/x-data"}]' >src/e2e_project_assets/assets/.ic-assets.json
+    assert_command dfx deploy
+    assert_command dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/data.bin";accept_encodings=vec{"identity"}})'
+    assert_match 'content_type = "application/x-data"'
+    assert_command dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/drafts/a.txt";accept_encodings=vec{"identity"}})'
+}
+
+@test "guesses the content type of files without an extension from their content" {
+    install_asset assetscanister
+    echo "/*  /index.html  200" >src/e2e_project_assets/assets/_redirects
+    echo '{"name": "e2e"}' >src/e2e_project_assets/assets/manifest-3f2a9c
+
+    dfx_start
+    assert_command dfx deploy
+
+    assert_command dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/_redirects";accept_encodings=vec{"identity"}})'
+    assert_match 'content_type = "text/plain"'
+    assert_command dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/manifest-3f2a9c";accept_encodings=vec{"identity"}})'
+    assert_match 'content_type = "application/json"'
+}
+
+@test "sync-assets lists the changes with --dry-run and then makes them" {
+    install_asset assetscanister
+    dfx_start
+    assert_command dfx deploy
+
+    echo "new file" >src/e2e_project_assets/assets/new.txt
+    echo "changed" >src/e2e_project_assets/assets/text-with-newlines.txt
+    rm src/e2e_project_assets/assets/binary/noise.txt
+    assert_command dfx build
+
+    assert_command dfx canister sync-assets e2e_project_assets --dry-run
+    assert_match "create /new.txt \(text/plain\)"
+    assert_match "set /text-with-newlines.txt \(sha256 [0-9a-f]+ -> [0-9a-f]+\)"
+    assert_match "delete /binary/noise.txt"
+    assert_match "[0-9]+ bytes to upload."
+    assert_command dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/binary/noise.txt";accept_encodings=vec{"identity"}})'
+    assert_command_fail dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/new.txt";accept_encodings=vec{"identity"}})'
+
+    assert_command dfx canister sync-assets e2e_project_assets
+    assert_command dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/new.txt";accept_encodings=vec{"identity"}})'
+    assert_command_fail dfx canister --no-wallet call --query e2e_project_assets get '(record{key="/binary/noise.txt";accept_encodings=vec{"identity"}})'
+
+    assert_command dfx canister sync-assets e2e_project_assets --dry-run
+    assert_match "The assets are up to date."
+    assert_match "0 bytes to upload."
+}
+
 @test "leaves in place files that were already installed" {
     install_asset assetscanister
     dd if=/dev/urandom of=src/e2e_project_assets/assets/asset1.bin bs=400000 count=1
@@ -138,14 +234,36 @@ teardown() {
     dfx_start
     assert_command dfx deploy
 
-    assert_match '/asset1.bin 1/1'
-    assert_match '/asset2.bin 1/1'
+    assert_not_match 'is already installed'
 
     dd if=/dev/urandom of=src/e2e_project_assets/assets/asset2.bin bs=400000 count=1
 
     assert_command dfx deploy
     assert_match '/asset1.bin.*is already installed'
-    assert_match '/asset2.bin 1/1'
+    assert_not_match '/asset2.bin.*is already installed'
+}
+
+@test "uploads the chunks of many assets" {
+    install_asset assetscanister
+    for i in $(seq 1 30); do
+      dd if=/dev/urandom of=src/e2e_project_assets/assets/asset$i.bin bs=100000 count=1
+    done
+    dd if=/dev/urandom of=src/e2e_project_assets/assets/large.bin bs=1000000 count=5
+    touch src/e2e_project_assets/assets/empty.bin
+
+    dfx_start
+    assert_command dfx deploy
+
+    ID=$(dfx canister id e2e_project_assets)
+    PORT=$(cat .dfx/webserver-port)
+    for i in 1 15 30; do
+      assert_command curl --fail --output asset$i.bin http://localhost:"$PORT"/asset$i.bin?canisterId="$ID"
+      diff asset$i.bin src/e2e_project_assets/assets/asset$i.bin
+    done
+    assert_command curl --fail --output large.bin http://localhost:"$PORT"/large.bin?canisterId="$ID"
+    diff large.bin src/e2e_project_assets/assets/large.bin
+    assert_command curl --fail --output empty.bin http://localhost:"$PORT"/empty.bin?canisterId="$ID"
+    diff empty.bin src/e2e_project_assets/assets/empty.bin
 }
 
 @test "unsets asset encodings that are removed from project" {
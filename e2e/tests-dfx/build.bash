@@ -65,6 +65,24 @@ teardown() {
     assert_match 'import error \[M0011\], canister alias "random" not defined'
 }
 
+@test "build reports diagnostics as json" {
+    install_asset warning
+    dfx_start
+    dfx canister create --all
+    assert_command dfx build --message-format json
+    assert_match '"reason":"canister-started","canister":"e2e_project"'
+    assert_match '"reason":"diagnostic","canister":"e2e_project","file":"[^"]*main.mo","range":\{"start":\{"line":[0-9]+,"column":[0-9]+\}'
+    assert_match '"severity":"warning","code":"M0145","message":"this pattern of type'
+    assert_match '"reason":"canister-finished","canister":"e2e_project","success":true,"fresh":false,"error":null'
+    assert_match '"reason":"build-finished","success":true'
+
+    install_asset import_error
+    assert_command_fail dfx build --message-format json
+    assert_match '"severity":"error","code":"M0011"'
+    assert_match '"reason":"canister-finished","canister":"e2e_project","success":false'
+    assert_match '"reason":"build-finished","success":false'
+}
+
 @test "build fails if canister type is not supported" {
   dfx_start
   dfx config canisters.e2e_project.type unknown_canister_type
@@ -84,6 +102,167 @@ teardown() {
   assert_command dfx canister call custom fromQuery
 }
 
+@test "can build a rust canister type" {
+  command -v cargo >/dev/null || skip "cargo is not installed"
+  install_asset rust_canister
+  dfx_start
+  dfx canister create --all
+  assert_command dfx build
+  assert_match "Executing 'cargo build --target wasm32-unknown-unknown --release -p rust_hello'"
+  test -f .dfx/local/canisters/rust_hello/rust_hello.wasm
+
+  dfx canister install --all
+  assert_command dfx canister call rust_hello hello
+  assert_eq '("hello")'
+}
+
+@test "build uses the selected build profile" {
+  install_asset custom_canister
+  dfx_start
+  dfx canister create --all
+  echo 'echo "PROFILE=$DFX_BUILD_PROFILE API=$API_URL"' >build.sh
+  cat <<<"$(jq '.canisters.custom.build="sh build.sh" | .profiles.staging={"codegen":"Release","env":{"API_URL":"https://staging"},"output":"dist"}' dfx.json)" >dfx.json
+
+  assert_command dfx build --profile staging
+  assert_match "PROFILE=staging API=https://staging"
+  test -f dist/custom/custom.wasm
+  test -f dist/custom/custom.did
+
+  assert_command dfx build
+  assert_match "PROFILE=Debug API="
+
+  assert_command_fail dfx build --profile prod
+  assert_match "Cannot find build profile 'prod'. Available profiles: Debug, Release, staging."
+}
+
+@test "build runs custom canister commands in their workdir with their env" {
+  install_asset custom_canister
+  dfx_start
+  dfx canister create --all
+  mkdir -p scripts
+  echo 'echo "DIR=$(basename "$PWD") NETWORK=$DFX_NETWORK GREETING=$GREETING"' >scripts/build.sh
+  cat <<<"$(jq '.canisters.custom.build="sh build.sh" | .canisters.custom.workdir="scripts" | .canisters.custom.env={"GREETING":"hello"} | .canisters.custom.inputs=["scripts/*.sh"]' dfx.json)" >dfx.json
+
+  assert_command dfx build
+  assert_match "DIR=scripts NETWORK=local GREETING=hello"
+
+  assert_command dfx build
+  assert_match "Canister 'custom' is up to date."
+
+  echo 'echo "Cannot find the compiler." >&2; exit 3' >scripts/build.sh
+  assert_command_fail dfx build
+  assert_match "The custom tool failed \(exit code 3\)"
+  assert_match "Cannot find the compiler."
+}
+
+@test "build strips wasm modules and reports their size" {
+  dfx_start
+  dfx canister create --all
+  cat <<<"$(jq '.profiles.small={"codegen":"Release","max_wasm_size":"1KB"}' dfx.json)" >dfx.json
+
+  assert_command dfx build --profile Release
+  assert_match "Wasm module of canister 'e2e_project': [0-9]+ bytes, [0-9]+ functions."
+  assert_match "code +[0-9]+ bytes"
+  assert_not_match "custom name"
+
+  assert_command_fail dfx build --profile small
+  assert_match "The Wasm module of canister 'e2e_project' is [0-9]+ bytes, more than the maximum of [0-9]+ bytes."
+}
+
+@test "build skips canisters that did not change" {
+  dfx_start
+  dfx canister create --all
+  assert_command dfx build e2e_project
+  assert_not_match "is up to date"
+
+  assert_command dfx build e2e_project
+  assert_match "Canister 'e2e_project' is up to date."
+
+  echo "// A change." >>src/e2e_project/main.mo
+  assert_command dfx build e2e_project
+  assert_not_match "is up to date"
+
+  assert_command dfx build e2e_project --force
+  assert_not_match "is up to date"
+}
+
+@test "build runs independent canisters in parallel with prefixed output" {
+  install_asset custom_canister
+  dfx_start
+  cat <<<"$(jq '.canisters.other=(.canisters.custom | .build="echo OTHER_CANISTER_BUILD_DONE")' dfx.json)" >dfx.json
+  dfx canister create --all
+
+  assert_command dfx build -j 2
+  assert_match "\\[custom\\] CUSTOM_CANISTER_BUILD_DONE"
+  assert_match "\\[other\\] OTHER_CANISTER_BUILD_DONE"
+
+  assert_command_fail dfx build -j 0
+  assert_match "Must be a positive number of jobs."
+}
+
+@test "build --watch rebuilds canisters when their sources change" {
+  dfx_start
+  dfx canister create --all
+  dfx build --watch >watch.log 2>&1 &
+  WATCH_PID=$!
+
+  for _ in $(seq 60); do grep -q "Watching for changes" watch.log && break; sleep 1; done
+  echo 'actor { public query func greet() : async Text { "watched" } }' >src/e2e_project/main.mo
+  for _ in $(seq 60); do [ "$(grep -c "Watching for changes" watch.log)" = 2 ] && break; sleep 1; done
+  kill "$WATCH_PID"
+
+  assert_command cat watch.log
+  assert_match "Changes detected in e2e_project. Rebuilding..."
+  assert_not_match "Changes detected in e2e_project_assets"
+  assert_command grep -c "Watching for changes" watch.log
+  assert_eq "2"
+}
+
+@test "build writes a manifest of the build artifacts" {
+  dfx_start
+  dfx canister create --all
+  assert_command dfx build
+
+  assert_command jq -r '.canisters[] | select(.name == "e2e_project") | .wasm' .dfx/local/canisters/build-manifest.json
+  assert_eq ".dfx/local/canisters/e2e_project/e2e_project.wasm"
+  assert_command jq -r '.canisters[] | select(.name == "e2e_project") | .wasm_sha256' .dfx/local/canisters/build-manifest.json
+  assert_eq "$(sha256sum .dfx/local/canisters/e2e_project/e2e_project.wasm | cut -d' ' -f1)"
+  assert_command jq -r '.canisters[] | select(.name == "e2e_project") | .canister_id' .dfx/local/canisters/build-manifest.json
+  assert_eq "$(dfx canister id e2e_project)"
+
+  echo "// A change." >>src/e2e_project/main.mo
+  assert_command dfx build e2e_project
+  assert_command jq -r '.canisters[].name' .dfx/local/canisters/build-manifest.json
+  assert_match "e2e_project_assets"
+  assert_command jq -r '.canisters[] | select(.name == "e2e_project") | .wasm_sha256' .dfx/local/canisters/build-manifest.json
+  assert_eq "$(sha256sum .dfx/local/canisters/e2e_project/e2e_project.wasm | cut -d' ' -f1)"
+
+  cat <<<"$(jq 'del(.canisters.e2e_project_assets)' dfx.json)" >dfx.json
+  assert_command dfx build e2e_project
+  assert_command jq -r '.canisters[].name' .dfx/local/canisters/build-manifest.json
+  assert_eq "e2e_project"
+
+  dfx canister install e2e_project
+  assert_command dfx canister info e2e_project
+  assert_match "Module hash: 0x$(jq -r '.canisters[] | select(.name == "e2e_project") | .wasm_sha256' .dfx/local/canisters/build-manifest.json)"
+}
+
+@test "build uses builder plugins for unknown canister types" {
+  install_asset custom_canister
+  install_asset builder_plugin
+  dfx_start
+  dfx canister create --all
+
+  assert_command_fail dfx build plugin
+  assert_match "Cannot find builder for canister 'plugin' of type 'example'."
+
+  assert_command env PATH="$(pwd)/bin:$PATH" dfx build plugin
+  assert_match "EXAMPLE_PLUGIN_BUILD plugin $(dfx canister id plugin)"
+  test -f .dfx/local/canisters/plugin/plugin.wasm
+
+  assert_command env PATH="$(pwd)/bin:$PATH" dfx canister install plugin
+}
+
 @test "build succeeds with network parameter" {
   dfx_start
   dfx canister --network local create --all
@@ -23,3 +23,91 @@ setup() {
     # We don't allow to change values that are non existent.
     assert_command_fail dfx config non_existent 123
 }
+
+@test "dfx config validate -- reports unknown fields with a suggestion" {
+    assert_command dfx config validate
+    assert_match "dfx.json is valid."
+
+    cat <<<"$(jq '.canisters.e2e_project.dependancies=[]' dfx.json)" >dfx.json
+    assert_command_fail dfx config validate
+    assert_match "canisters.e2e_project.dependancies: Unknown field 'dependancies'. Did you mean 'dependencies'?"
+
+    assert_command dfx config schema
+    assert_match '"title": "dfx.json"'
+}
+
+@test "dfx config -- resolves variables from the environment and .env" {
+    cat <<<"$(jq '.networks.local.bind="${E2E_HOST:-127.0.0.1}:${E2E_PORT}"' dfx.json)" >dfx.json
+    echo "E2E_PORT=8765" >.env
+
+    assert_command dfx config networks.local.bind
+    assert_eq '"${E2E_HOST:-127.0.0.1}:${E2E_PORT}"'
+
+    assert_command dfx config --resolved networks.local.bind
+    assert_eq '"127.0.0.1:8765"'
+
+    E2E_HOST=localhost assert_command dfx config --resolved networks.local.bind
+    assert_eq '"localhost:8765"'
+
+    rm .env
+    assert_command dfx config networks.local.bind
+    assert_eq '"${E2E_HOST:-127.0.0.1}:${E2E_PORT}"'
+    assert_command dfx config --resolved canisters
+    assert_command_fail dfx config --resolved networks.local.bind
+    assert_match "Unresolved variable 'E2E_PORT' in 'networks.local.bind'"
+
+    assert_command dfx config networks.local.bind '"127.0.0.1:${E2E_PORT:-8765}"'
+    assert_command dfx config --resolved networks.local.bind
+    assert_eq '"127.0.0.1:8765"'
+}
+
+@test "dfx config -- unresolved variables only fail the commands using them" {
+    cat <<<"$(jq '.networks.staging.providers=["https://${E2E_STAGING_HOST}"]' dfx.json)" >dfx.json
+
+    # The canister was not created on ic, but the configuration of ic is usable.
+    assert_command_fail dfx canister --network ic id e2e_project
+    assert_not_match "E2E_STAGING_HOST"
+    assert_command dfx config --resolved networks.local
+    assert_command_fail dfx canister --network staging id e2e_project
+    assert_match "Unresolved variable 'E2E_STAGING_HOST' in 'networks.staging.providers.0'"
+}
+
+@test "dfx config migrate -- updates an old configuration layout" {
+    install_asset config_v1
+
+    assert_command_fail dfx canister id e2e_project
+    assert_match "uses version 1 of the configuration layout"
+
+    assert_command dfx config migrate --dry-run
+    assert_match '\-  "version": 1'
+    assert_match '\+  "version": 2'
+    assert_match '\+      "type": "ephemeral"'
+    assert_match '\+      "type": "persistent"'
+    assert_eq "1" "$(jq .version dfx.json)"
+
+    assert_command dfx config migrate
+    assert_eq "2" "$(jq .version dfx.json)"
+    assert_eq '"ephemeral"' "$(jq .networks.local.type dfx.json)"
+    assert_eq '"persistent"' "$(jq .networks.staging.type dfx.json)"
+    assert_eq "null" "$(jq .defaults.build.output dfx.json)"
+    assert_eq '""' "$(jq .defaults.build.packtool dfx.json)"
+
+    assert_command dfx config migrate
+    assert_match "already at version 2"
+
+    assert_command_fail dfx canister id e2e_project
+    assert_not_match "configuration layout"
+}
+
+@test "dfx config migrate -- treats a configuration without version as version 1" {
+    install_asset config_unversioned
+
+    assert_command_fail dfx canister id e2e_project
+    assert_match "uses version 1 of the configuration layout"
+    DFX_WARNING=-config_version assert_command_fail dfx canister id e2e_project
+    assert_not_match "configuration layout"
+
+    assert_command dfx config migrate
+    assert_eq "2" "$(jq .version dfx.json)"
+    assert_eq '"ephemeral"' "$(jq .networks.local.type dfx.json)"
+}
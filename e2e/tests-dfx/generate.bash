@@ -0,0 +1,55 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    # We want to work from a temporary directory, different for every test.
+    cd "$(mktemp -d -t dfx-e2e-XXXXXXXX)" || exit
+
+    dfx_new
+}
+
+teardown() {
+    dfx_stop
+}
+
+@test "generate writes the default bindings of all canisters" {
+    assert_command dfx generate --check
+    assert_match "Generated the declarations of canister e2e_project"
+
+    test -f src/declarations/e2e_project/e2e_project.did
+    test -f src/declarations/e2e_project/e2e_project.did.js
+    test -f src/declarations/e2e_project/e2e_project.did.d.ts
+    test -f src/declarations/e2e_project/index.js
+    test -f src/declarations/e2e_project/index.d.ts
+    test ! -f src/declarations/e2e_project/e2e_project.mo
+}
+
+@test "generate requires the canisters to be created, unless checking" {
+    assert_command_fail dfx generate
+    test ! -d src/declarations/e2e_project
+
+    assert_command dfx generate --check
+    test -f src/declarations/e2e_project/e2e_project.did
+    test ! -f .dfx/local/canisters/e2e_project/e2e_project.wasm
+}
+
+@test "generate uses the declarations config and the canister ID of the network" {
+    dfx_start
+    dfx canister create e2e_project
+    cat <<<"$(jq '.canisters.e2e_project.declarations={"output":"bindings","bindings":["js","mo","rs"]}' dfx.json)" >dfx.json
+
+    assert_command dfx generate e2e_project
+    test -f bindings/e2e_project.did.js
+    test -f bindings/e2e_project.mo
+    test -f bindings/e2e_project.rs
+    test ! -f bindings/e2e_project.did.d.ts
+    assert_command cat bindings/index.js
+    assert_match "$(dfx canister id e2e_project)"
+    assert_match "CANISTER_ID_e2e_project"
+}
+
+@test "generate fails with an unknown binding" {
+    cat <<<"$(jq '.canisters.e2e_project.declarations={"bindings":["py"]}' dfx.json)" >dfx.json
+    assert_command_fail dfx generate e2e_project
+}
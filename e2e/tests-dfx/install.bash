@@ -52,3 +52,43 @@ teardown() {
 
     assert_match "ComputeNetworkNotFound.*nosuch"
 }
+
+@test "upgrade fails when the new interface breaks its clients" {
+    dfx_start
+    dfx canister create e2e_project
+    dfx build e2e_project
+    dfx canister install e2e_project
+
+    echo 'actor { public func count() : async Nat { 0 } }' >src/e2e_project/main.mo
+    dfx build e2e_project
+
+    assert_command_fail dfx canister install e2e_project --mode upgrade
+    assert_match "The upgrade of canister e2e_project breaks the clients of its interface"
+    assert_match "greet"
+    assert_match "The method was removed."
+    assert_match "Use --allow-breaking to upgrade anyway."
+
+    assert_command dfx canister install e2e_project --mode upgrade --allow-breaking
+    assert_match "Upgrading code for canister e2e_project"
+
+    assert_command dfx canister install e2e_project --mode upgrade
+}
+
+@test "upgrade fails when the stable variables are not compatible" {
+    dfx_start
+    "$(dfx cache show)/moc" --help | grep -q -- --stable-types || skip "moc does not support --stable-types"
+    echo 'actor { stable var state : Nat = 0; public query func greet(name : Text) : async Text { name } }' >src/e2e_project/main.mo
+    dfx canister create e2e_project
+    dfx build e2e_project
+    dfx canister install e2e_project
+    test -f .dfx/local/canisters/e2e_project/deployed.most
+
+    echo 'actor { stable var state : Text = ""; public query func greet(name : Text) : async Text { name } }' >src/e2e_project/main.mo
+    dfx build e2e_project
+
+    assert_command_fail dfx canister install e2e_project --mode upgrade
+    assert_match "The stable variables of canister e2e_project are not compatible"
+    assert_match "Use --mode reinstall to discard the state of the canister."
+
+    assert_command dfx canister install e2e_project --mode reinstall
+}
@@ -0,0 +1,33 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    # We want to work from a temporary directory, different for every test.
+    cd "$(mktemp -d -t dfx-e2e-XXXXXXXX)" || exit
+
+    dfx_new
+}
+
+teardown() {
+    dfx_stop
+}
+
+@test "build embeds the metadata of canisters in their wasm module" {
+    dfx_start
+    dfx canister create --all
+    dfx build
+
+    WASM=.dfx/local/canisters/e2e_project/e2e_project.wasm
+    assert_command grep -c "icp:public candid:service" "$WASM"
+    assert_command grep -c "icp:public dfx:version" "$WASM"
+    assert_command grep -c "icp:public dfx:builder" "$WASM"
+}
+
+@test "metadata is kept when stripping the wasm module" {
+    dfx_start
+    dfx canister create --all
+    dfx build --profile Release
+
+    assert_command grep -c "icp:public dfx:builder" .dfx/local/canisters/e2e_project/e2e_project.wasm
+}
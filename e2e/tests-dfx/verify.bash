@@ -0,0 +1,38 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    # We want to work from a temporary directory, different for every test.
+    cd "$(mktemp -d -t dfx-e2e-XXXXXXXX)" || exit
+
+    dfx_new
+}
+
+teardown() {
+    dfx_stop
+}
+
+@test "verify reports whether installed modules match the local builds" {
+    dfx_start
+    dfx canister create --all
+    dfx build
+    assert_command dfx canister verify e2e_project
+    assert_match "e2e_project: empty"
+
+    dfx canister install e2e_project
+    assert_command dfx canister verify e2e_project
+    assert_match "e2e_project: match"
+
+    echo 'actor { public query func greet(name : Text) : async Text { "changed" } }' >src/e2e_project/main.mo
+    assert_command_fail dfx canister verify e2e_project --build
+    assert_match "e2e_project: mismatch"
+    assert_match "The installed modules of e2e_project do not match their local builds."
+}
+
+@test "verify fails without a build" {
+    dfx_start
+    dfx canister create --all
+    assert_command_fail dfx canister verify --all
+    assert_match "Did you forget to run \`dfx build\`?"
+}
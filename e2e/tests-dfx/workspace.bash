@@ -0,0 +1,61 @@
+#!/usr/bin/env bats
+
+load ../utils/_
+
+setup() {
+    # We want to work from a temporary directory, different for every test.
+    cd "$(mktemp -d -t dfx-e2e-XXXXXXXX)" || exit
+
+    dfx_new
+}
+
+teardown() {
+    dfx_stop
+}
+
+@test "a workspace builds the canisters of all its members together" {
+    install_asset workspace
+    dfx_start
+    dfx canister create --all
+
+    # Building a canister builds its dependencies in other members.
+    assert_command dfx build frontend.app
+    assert_command dfx canister install --all
+
+    assert_command dfx canister call backend.api greet World
+    assert_eq '("Hello, World from store!")'
+
+    # Canisters of other members are imported with `_` instead of `.`.
+    assert_command dfx canister call frontend.app greet World
+    assert_eq '("Hello, World from store!")'
+
+    # Commands run in a member directory apply to the whole workspace.
+    cd backend
+    assert_command dfx canister call frontend.app name
+    assert_eq '("app")'
+}
+
+@test "dfx config in a workspace member reads and writes the file of the member" {
+    install_asset workspace
+    cd backend
+
+    assert_command dfx config canisters.api.main
+    assert_eq '"api.mo"'
+    assert_command dfx config canisters.store.main store2.mo
+    assert_command jq -r .canisters.store.main dfx.json
+    assert_eq "store2.mo"
+    assert_command jq -r .canisters ../dfx.json
+    assert_eq "null"
+
+    assert_command dfx config migrate
+    assert_match "dfx.json is already at version 2."
+    assert_command dfx config validate
+}
+
+@test "workspace members must exist" {
+    install_asset workspace
+    cat <<<"$(jq '.workspace.members+=["missing"]' dfx.json)" >dfx.json
+
+    assert_command_fail dfx build --all
+    assert_match "Cannot load workspace member 'missing'"
+}
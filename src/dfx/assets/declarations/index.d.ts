@@ -0,0 +1,18 @@
+import type { ActorConfig, ActorSubclass, HttpAgentOptions } from "@dfinity/agent";
+import type { IDL } from "@dfinity/candid";
+import type { Principal } from "@dfinity/principal";
+
+import type { _SERVICE } from "./{canister_name}.did";
+
+export declare const idlFactory: IDL.InterfaceFactory;
+export declare const canisterId: string;
+
+export declare const createActor: (
+  canisterId: string | Principal,
+  options?: {
+    agentOptions?: HttpAgentOptions;
+    actorOptions?: ActorConfig;
+  }
+) => ActorSubclass<_SERVICE>;
+
+export declare const {canister_ident}: ActorSubclass<_SERVICE>;
@@ -0,0 +1,34 @@
+import { Actor, HttpAgent } from "@dfinity/agent";
+
+import { idlFactory } from "./{canister_name}.did.js";
+export { idlFactory };
+
+// The canister ID on the network the declarations were generated for. The bundler can
+// replace it with the CANISTER_ID_{canister_ident} environment variable.
+export const canisterId =
+  process.env.CANISTER_ID_{canister_ident} || "{canister_id}";
+
+/**
+ * Creates an actor of the canister.
+ * @param {string | import("@dfinity/principal").Principal} canisterId
+ * @param {{ agentOptions?: import("@dfinity/agent").HttpAgentOptions, actorOptions?: import("@dfinity/agent").ActorConfig }} [options]
+ */
+export const createActor = (canisterId, options = {}) => {
+  const agent = new HttpAgent(options.agentOptions);
+
+  // Only the Internet Computer has a root key that does not need to be fetched.
+  if ({fetch_root_key}) {
+    agent.fetchRootKey().catch((err) => {
+      console.warn("Unable to fetch the root key. Is the local replica running?");
+      console.error(err);
+    });
+  }
+
+  return Actor.createActor(idlFactory, {
+    agent,
+    canisterId,
+    ...options.actorOptions,
+  });
+};
+
+export const {canister_ident} = createActor(canisterId);
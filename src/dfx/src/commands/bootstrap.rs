@@ -48,7 +48,7 @@ pub struct BootstrapOpts {
 pub fn exec(env: &dyn Environment, opts: BootstrapOpts) -> DfxResult {
     let logger = env.get_logger();
     let config = env.get_config_or_anyhow()?;
-    let config_defaults = get_config_defaults_from_file(env);
+    let config_defaults = get_config_defaults_from_file(env)?;
     let base_config_bootstrap = config_defaults.get_bootstrap().to_owned();
     let config_bootstrap = apply_arguments(&base_config_bootstrap, env, opts.clone())?;
 
@@ -115,9 +115,10 @@ fn apply_arguments(
 
 /// Gets the configuration options for the bootstrap server as they were specified in the dfx
 /// configuration file.
-fn get_config_defaults_from_file(env: &dyn Environment) -> ConfigDefaults {
-    env.get_config().map_or(Default::default(), |config| {
-        config.get_config().get_defaults().to_owned()
+fn get_config_defaults_from_file(env: &dyn Environment) -> DfxResult<ConfigDefaults> {
+    env.get_config().map_or(Ok(Default::default()), |config| {
+        config.get_config().check_resolved("defaults.bootstrap")?;
+        Ok(config.get_config().get_defaults().to_owned())
     })
 }
 
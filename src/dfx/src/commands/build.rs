@@ -1,9 +1,12 @@
 use crate::lib::builders::BuildConfig;
+use crate::lib::diagnostics::MessageFormat;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::models::canister::CanisterPool;
 use crate::lib::models::canister_id_store::CanisterIdStore;
 use crate::lib::provider::create_agent_environment;
+use crate::lib::watch::watch_canisters;
+use crate::util::clap::validators::jobs_validator;
 
 use clap::Clap;
 
@@ -22,6 +25,30 @@ pub struct CanisterBuildOpts {
     #[clap(long)]
     check: bool,
 
+    /// Specifies the build profile: Debug, Release or the name of a profile defined in dfx.json.
+    /// By default, the profile set in dfx.json is used.
+    #[clap(long)]
+    profile: Option<String>,
+
+    /// Builds every canister, even those that did not change since their last build.
+    #[clap(long)]
+    force: bool,
+
+    /// Builds at most this many independent canisters at the same time.
+    #[clap(long, short('j'), default_value("1"), validator(jobs_validator))]
+    jobs: usize,
+
+    /// Keeps running after the build, and rebuilds the canisters whose source files change
+    /// along with the canisters depending on them.
+    #[clap(long)]
+    watch: bool,
+
+    /// Specifies how to report the progress of the build. With "json", each diagnostic of the
+    /// compilers and the start and result of each canister build are written to stdout as
+    /// one JSON object per line.
+    #[clap(long, default_value("human"), possible_values(&["human", "json"]))]
+    message_format: String,
+
     /// Override the compute network to connect to. By default, the local network is used.
     /// A valid URL (starting with `http:` or `https:`) can be used here, and a special
     /// ephemeral network will be created specifically for this request. E.g.
@@ -70,9 +97,19 @@ pub fn exec(env: &dyn Environment, opts: CanisterBuildOpts) -> DfxResult {
 
     slog::info!(logger, "Building canisters...");
 
-    canister_pool.build_or_fail(
-        BuildConfig::from_config(&config)?.with_build_mode_check(build_mode_check),
-    )?;
+    let build_config = BuildConfig::from_config(&config, opts.profile.as_deref())?
+        .with_build_mode_check(build_mode_check)
+        .with_force(opts.force)
+        .with_jobs(opts.jobs)
+        .with_message_format(MessageFormat::from_name(&opts.message_format));
 
-    Ok(())
+    if !opts.watch {
+        return canister_pool.build_or_fail(build_config);
+    }
+
+    // A failed build is reported, and fixed by the next change.
+    if let Err(e) = canister_pool.build_or_fail(build_config.clone()) {
+        slog::error!(logger, "{:#}", e);
+    }
+    watch_canisters(logger, &canister_pool, build_config, |_| Ok(()))
 }
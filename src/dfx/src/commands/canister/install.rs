@@ -39,6 +39,10 @@ pub struct CanisterInstallOpts {
     /// Specifies the data type for the argument when making the call using an argument.
     #[clap(long, requires("argument"), possible_values(&["idl", "raw"]))]
     argument_type: Option<String>,
+
+    /// Upgrades the canister even if its new interface breaks the clients of the installed one.
+    #[clap(long)]
+    allow_breaking: bool,
 }
 
 pub async fn exec(
@@ -76,6 +80,7 @@ pub async fn exec(
             mode,
             timeout,
             call_sender,
+            opts.allow_breaking,
         )
         .await
     } else if opts.all {
@@ -96,6 +101,7 @@ pub async fn exec(
                     mode,
                     timeout,
                     call_sender,
+                    opts.allow_breaking,
                 )
                 .await?;
             }
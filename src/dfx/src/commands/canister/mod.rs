@@ -19,8 +19,10 @@ mod sign;
 mod start;
 mod status;
 mod stop;
+mod sync_assets;
 mod uninstall_code;
 mod update_settings;
+mod verify;
 
 /// Manages canisters deployed on a network replica.
 #[derive(Clap)]
@@ -62,8 +64,10 @@ enum SubCommand {
     Start(start::CanisterStartOpts),
     Status(status::CanisterStatusOpts),
     Stop(stop::CanisterStopOpts),
+    SyncAssets(sync_assets::CanisterSyncAssetsOpts),
     UninstallCode(uninstall_code::UninstallCodeOpts),
     UpdateSettings(update_settings::UpdateSettingsOpts),
+    Verify(verify::CanisterVerifyOpts),
 }
 
 pub fn exec(env: &dyn Environment, opts: CanisterOpts) -> DfxResult {
@@ -96,10 +100,12 @@ pub fn exec(env: &dyn Environment, opts: CanisterOpts) -> DfxResult {
             SubCommand::Start(v) => start::exec(&agent_env, v, &call_sender).await,
             SubCommand::Status(v) => status::exec(&agent_env, v, &call_sender).await,
             SubCommand::Stop(v) => stop::exec(&agent_env, v, &call_sender).await,
+            SubCommand::SyncAssets(v) => sync_assets::exec(&agent_env, v).await,
             SubCommand::UninstallCode(v) => uninstall_code::exec(&agent_env, v, &call_sender).await,
             SubCommand::UpdateSettings(v) => {
                 update_settings::exec(&agent_env, v, &call_sender).await
             }
+            SubCommand::Verify(v) => verify::exec(&agent_env, v).await,
         }
     })
 }
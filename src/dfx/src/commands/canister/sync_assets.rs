@@ -0,0 +1,54 @@
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::installers::assets::sync_assets;
+use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::root_key::fetch_root_key_if_needed;
+use crate::util::expiry_duration;
+
+use anyhow::{anyhow, bail};
+use clap::Clap;
+use slog::info;
+
+/// Uploads the assets of an assets canister that changed since it was installed, without
+/// installing its code again.
+#[derive(Clap)]
+pub struct CanisterSyncAssetsOpts {
+    /// Specifies the name of the assets canister.
+    canister: String,
+
+    /// Lists the operations that would create, delete, set or unset the contents of assets,
+    /// and the bytes to upload, without changing the canister.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+pub async fn exec(env: &dyn Environment, opts: CanisterSyncAssetsOpts) -> DfxResult {
+    let config = env.get_config_or_anyhow()?;
+    let agent = env
+        .get_agent()
+        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
+    let timeout = expiry_duration();
+
+    fetch_root_key_if_needed(env).await?;
+
+    let canister_id_store = CanisterIdStore::for_env(env)?;
+    let canister_id = canister_id_store.get(&opts.canister)?;
+    let canister_info = CanisterInfo::load(&config, &opts.canister, Some(canister_id))?;
+    if canister_info.get_type() != "assets" {
+        bail!("Canister {} is not an assets canister.", opts.canister);
+    }
+
+    if opts.dry_run {
+        info!(
+            env.get_logger(),
+            "Changes to the assets of canister {}:", opts.canister
+        );
+    } else {
+        info!(
+            env.get_logger(),
+            "Uploading assets to asset canister {}...", opts.canister
+        );
+    }
+    sync_assets(env, &canister_info, &agent, timeout, opts.dry_run).await
+}
@@ -0,0 +1,110 @@
+use crate::lib::builders::BuildConfig;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::models::build_manifest::BuildManifest;
+use crate::lib::models::canister::CanisterPool;
+use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::root_key::fetch_root_key_if_needed;
+
+use anyhow::{anyhow, bail};
+use clap::Clap;
+use ic_agent::AgentError;
+use slog::{info, warn};
+
+/// Checks that the modules installed in canisters are the ones built locally, by comparing
+/// their certified module hash to the SHA-256 of the built Wasm modules.
+#[derive(Clap)]
+pub struct CanisterVerifyOpts {
+    /// Specifies the name of the canister to verify.
+    /// You must specify either a canister name or the --all flag.
+    canister: Option<String>,
+
+    /// Verifies all of the canisters configured in the dfx.json file.
+    #[clap(long, required_unless_present("canister"))]
+    all: bool,
+
+    /// Builds the canisters before verifying them. By default, the modules of the last build
+    /// are used, as listed in its build manifest.
+    #[clap(long)]
+    build: bool,
+
+    /// Specifies the build profile used with --build.
+    #[clap(long, requires("build"))]
+    profile: Option<String>,
+}
+
+pub async fn exec(env: &dyn Environment, opts: CanisterVerifyOpts) -> DfxResult {
+    let config = env.get_config_or_anyhow()?;
+    let agent = env
+        .get_agent()
+        .ok_or_else(|| anyhow!("Cannot get HTTP client from environment."))?;
+
+    let canister_names = match opts.canister.as_deref() {
+        Some(canister) => vec![canister.to_string()],
+        None => config
+            .get_config()
+            .canisters
+            .as_ref()
+            .map(|canisters| canisters.keys().cloned().collect())
+            .unwrap_or_default(),
+    };
+
+    let build_config = BuildConfig::from_config(&config, opts.profile.as_deref())?;
+    if opts.build {
+        let canister_names = config
+            .get_config()
+            .get_canister_names_with_dependencies(opts.canister.as_deref())?;
+        let canister_pool = CanisterPool::load(env, false, &canister_names)?;
+        canister_pool.build_or_fail(build_config.clone())?;
+    }
+    let manifest = BuildManifest::load(&build_config.build_root)?;
+
+    fetch_root_key_if_needed(env).await?;
+
+    let log = env.get_logger();
+    let canister_id_store = CanisterIdStore::for_env(env)?;
+    let mut mismatches = vec![];
+    for canister_name in &canister_names {
+        let canister_id = canister_id_store.get(canister_name)?;
+        let built_hash = manifest.get_wasm_sha256(canister_name).ok_or_else(|| {
+            anyhow!(
+                "Canister {} is not in the build manifest. Did you forget to run `dfx build`?",
+                canister_name
+            )
+        })?;
+
+        match agent
+            .read_state_canister_info(canister_id, "module_hash")
+            .await
+        {
+            Ok(blob) => {
+                let installed_hash = hex::encode(&blob);
+                if installed_hash == built_hash {
+                    info!(log, "{}: match (0x{})", canister_name, built_hash);
+                } else {
+                    warn!(
+                        log,
+                        "{}: mismatch (installed 0x{}, built 0x{})",
+                        canister_name,
+                        installed_hash,
+                        built_hash
+                    );
+                    mismatches.push(canister_name.as_str());
+                }
+            }
+            // If the canister is empty, this path does not exist.
+            Err(AgentError::LookupPathUnknown(_)) | Err(AgentError::LookupPathAbsent(_)) => {
+                info!(log, "{}: empty (built 0x{})", canister_name, built_hash);
+            }
+            Err(x) => bail!(x),
+        }
+    }
+
+    if !mismatches.is_empty() {
+        bail!(
+            "The installed modules of {} do not match their local builds.",
+            mismatches.join(", ")
+        );
+    }
+    Ok(())
+}
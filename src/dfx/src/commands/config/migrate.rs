@@ -0,0 +1,159 @@
+use crate::config::dfinity::Config;
+use crate::config::migration::{config_version, migrate, CURRENT_CONFIG_VERSION};
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+
+use anyhow::Context;
+use clap::Clap;
+use slog::info;
+
+/// Number of unchanged lines shown around each change of the diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Updates dfx.json to the configuration layout of this version of dfx.
+#[derive(Clap)]
+pub struct ConfigMigrateOpts {
+    /// Displays the changes that would be made to dfx.json, without writing them.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+pub fn exec(env: &dyn Environment, opts: ConfigMigrateOpts) -> DfxResult {
+    // In a workspace member, this is the file of the member rather than of the workspace.
+    let mut config = Config::from_nearest_file(&std::env::current_dir()?)
+        .context("Cannot find dfx configuration file in the current working directory.")?;
+    let log = env.get_logger();
+
+    let version = config_version(config.get_json())?;
+    let (migrated, applied) = migrate(config.get_json())?;
+    if version == CURRENT_CONFIG_VERSION && applied.is_empty() {
+        info!(
+            log,
+            "{} is already at version {}.",
+            config.get_path().display(),
+            CURRENT_CONFIG_VERSION
+        );
+        return Ok(());
+    }
+
+    info!(
+        log,
+        "Migrating {} from version {} to version {}:",
+        config.get_path().display(),
+        version,
+        CURRENT_CONFIG_VERSION
+    );
+    for description in applied {
+        info!(log, "  {}", description);
+    }
+
+    if opts.dry_run {
+        let before = serde_json::to_string_pretty(config.get_json())?;
+        let after = serde_json::to_string_pretty(&migrated)?;
+        println!("--- {}", config.get_path().display());
+        println!("+++ {}", config.get_path().display());
+        for line in diff_lines(&before, &after) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    *config.get_mut_json() = migrated;
+    config.save()
+}
+
+/// A line-based diff of two texts in the unified format, with `DIFF_CONTEXT` lines of
+/// context around changes.
+fn diff_lines(before: &str, after: &str) -> Vec<String> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+
+    // Longest common subsequence table, lcs[i][j] for the suffixes a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Each edit is (prefix, line, index in a, index in b).
+    let mut edits = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            edits.push((' ', a[i], i, j));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            edits.push(('-', a[i], i, j));
+            i += 1;
+        } else {
+            edits.push(('+', b[j], i, j));
+            j += 1;
+        }
+    }
+
+    // Group the changes into hunks, merging those separated by little context.
+    let changes: Vec<usize> = (0..edits.len()).filter(|&k| edits[k].0 != ' ').collect();
+    let mut output = vec![];
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(DIFF_CONTEXT);
+        let mut end = changes[k];
+        while k + 1 < changes.len() && changes[k + 1] <= end + 2 * DIFF_CONTEXT + 1 {
+            k += 1;
+            end = changes[k];
+        }
+        let end = (end + DIFF_CONTEXT + 1).min(edits.len());
+        k += 1;
+
+        let hunk = &edits[start..end];
+        let count = |prefix: char| hunk.iter().filter(|e| e.0 == ' ' || e.0 == prefix).count();
+        output.push(format!(
+            "@@ -{},{} +{},{} @@",
+            hunk[0].2 + 1,
+            count('-'),
+            hunk[0].3 + 1,
+            count('+')
+        ));
+        output.extend(
+            hunk.iter()
+                .map(|(prefix, line, _, _)| format!("{}{}", prefix, line)),
+        );
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_shows_changes_with_context() {
+        let before = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj";
+        let after = "a\nb\nc\nd\nE\nf\ng\nh\ni\nj\nk";
+
+        assert_eq!(
+            diff_lines(before, after),
+            vec![
+                "@@ -2,9 +2,10 @@",
+                " b",
+                " c",
+                " d",
+                "-e",
+                "+E",
+                " f",
+                " g",
+                " h",
+                " i",
+                " j",
+                "+k",
+            ]
+        );
+        assert!(diff_lines(before, before).is_empty());
+    }
+}
@@ -2,17 +2,21 @@ use crate::config::dfinity::Config;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 
-use anyhow::{anyhow, bail};
+use anyhow::{anyhow, bail, Context};
 use clap::Clap;
 use serde_json::value::Value;
 
+mod migrate;
+mod schema;
+mod validate;
+
 /// Configures project options for your currently-selected project.
 #[derive(Clap)]
 pub struct ConfigOpts {
     /// Specifies the name of the configuration option to set or read.
     /// Use the period delineated path to specify the option to set or read.
     /// If this is not mentioned, outputs the whole configuration.
-    config_path: String,
+    config_path: Option<String>,
 
     /// Specifies the new value to set.
     /// If you don't specify a value, the command displays the current value of the option from the configuration file.
@@ -21,13 +25,36 @@ pub struct ConfigOpts {
     /// Specifies the format of the output. By default, the output format is JSON.
     #[clap(long, default_value("json"), possible_values(&["json", "text"]))]
     format: String,
+
+    /// Displays the value with the variables (e.g. `${NAME}`) resolved, rather than as
+    /// written in the configuration file.
+    #[clap(long, conflicts_with("value"))]
+    resolved: bool,
+
+    #[clap(subcommand)]
+    subcmd: Option<SubCommand>,
+}
+
+#[derive(Clap)]
+enum SubCommand {
+    Migrate(migrate::ConfigMigrateOpts),
+    Schema(schema::ConfigSchemaOpts),
+    Validate(validate::ConfigValidateOpts),
 }
 
 pub fn exec(env: &dyn Environment, opts: ConfigOpts) -> DfxResult {
-    // Cannot use the `env` variable as we need a mutable copy.
-    let mut config: Config = env.get_config_or_anyhow()?.as_ref().clone();
+    match opts.subcmd {
+        Some(SubCommand::Migrate(v)) => return migrate::exec(env, v),
+        Some(SubCommand::Schema(v)) => return schema::exec(env, v),
+        Some(SubCommand::Validate(v)) => return validate::exec(env, v),
+        None => {}
+    }
+
+    // In a workspace member, this is the file of the member rather than of the workspace.
+    let mut config = Config::from_nearest_file(&std::env::current_dir()?)
+        .context("Cannot find dfx configuration file in the current working directory.")?;
 
-    let config_path = opts.config_path.as_str();
+    let config_path = opts.config_path.as_deref().unwrap_or("");
     let format = opts.format.as_str();
 
     // We replace `.` with `/` so the user can use `path.value.field` instead of forcing him
@@ -44,6 +71,15 @@ pub fn exec(env: &dyn Environment, opts: ConfigOpts) -> DfxResult {
         config_path.clear()
     }
 
+    let json = if opts.resolved {
+        config
+            .get_config()
+            .check_resolved(&config_path.trim_start_matches('/').replace("/", "."))?;
+        config.get_resolved_json().clone()
+    } else {
+        config.get_json().clone()
+    };
+
     if let Some(arg_value) = opts.value {
         // Try to parse the type of the value (which is a string from the arguments) as
         // JSON. By default we will just assume the type is string (if all parsing fails).
@@ -54,7 +90,7 @@ pub fn exec(env: &dyn Environment, opts: ConfigOpts) -> DfxResult {
             .pointer_mut(config_path.as_str())
             .ok_or_else(|| anyhow!("Config path does not exist at '{}'.", config_path))? = value;
         config.save()
-    } else if let Some(value) = config.get_json().pointer(config_path.as_str()) {
+    } else if let Some(value) = json.pointer(config_path.as_str()) {
         match format {
             "text" => println!("{}", value),
             "json" => println!("{}", serde_json::to_string_pretty(value)?),
@@ -0,0 +1,22 @@
+use crate::config::schema::dfx_json_schema;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+
+use clap::Clap;
+
+/// Outputs a JSON Schema document describing dfx.json, for use by editors.
+#[derive(Clap)]
+pub struct ConfigSchemaOpts {}
+
+pub fn exec(_env: &dyn Environment, _opts: ConfigSchemaOpts) -> DfxResult {
+    let mut schema = dfx_json_schema().to_json_schema();
+    if let Some(object) = schema.as_object_mut() {
+        object.insert(
+            "$schema".to_string(),
+            "http://json-schema.org/draft-07/schema#".into(),
+        );
+        object.insert("title".to_string(), "dfx.json".into());
+    }
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::config::dfinity::{format_diagnostics, Config};
+use crate::config::schema::validate_str;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+
+use anyhow::{bail, Context};
+use clap::Clap;
+
+/// Checks dfx.json against the known configuration layout, reporting the location of
+/// every problem found.
+#[derive(Clap)]
+pub struct ConfigValidateOpts {
+    /// Specifies the format of the output. By default, the output format is text.
+    #[clap(long, default_value("text"), possible_values(&["json", "text"]))]
+    format: String,
+}
+
+pub fn exec(_env: &dyn Environment, opts: ConfigValidateOpts) -> DfxResult {
+    // Read the file directly, since the environment cannot load a config that is not
+    // valid enough to deserialize.
+    let path = Config::resolve_config_path(&std::env::current_dir()?)
+        .context("Cannot find dfx configuration file in the current working directory.")?;
+    let content = std::fs::read_to_string(&path)?;
+    let diagnostics = validate_str(&content)
+        .with_context(|| format!("Cannot parse {} as JSON.", path.display()))?;
+
+    match opts.format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&diagnostics)?),
+        _ => {
+            for line in format_diagnostics(&path, &diagnostics) {
+                println!("{}", line);
+            }
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        bail!(
+            "Found {} problem(s) in {}.",
+            diagnostics.len(),
+            path.display()
+        );
+    }
+    if opts.format != "json" {
+        println!("{} is valid.", path.display());
+    }
+    Ok(())
+}
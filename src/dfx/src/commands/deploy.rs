@@ -1,12 +1,13 @@
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 use crate::lib::identity::identity_utils::call_sender;
-use crate::lib::operations::canister::deploy_canisters;
+use crate::lib::operations::canister::{deploy_canisters, watch_deployed_canisters};
 use crate::lib::provider::create_agent_environment;
 use crate::lib::root_key::fetch_root_key_if_needed;
-use crate::util::clap::validators::cycle_amount_validator;
+use crate::util::clap::validators::{cycle_amount_validator, jobs_validator};
 use crate::util::expiry_duration;
 
+use anyhow::bail;
 use clap::Clap;
 use tokio::runtime::Runtime;
 
@@ -32,6 +33,28 @@ pub struct DeployOpts {
     #[clap(long)]
     network: Option<String>,
 
+    /// Specifies the build profile: Debug, Release or the name of a profile defined in dfx.json.
+    /// By default, the profile set in dfx.json is used.
+    #[clap(long)]
+    profile: Option<String>,
+
+    /// Builds every canister, even those that did not change since their last build.
+    #[clap(long)]
+    force: bool,
+
+    /// Builds at most this many independent canisters at the same time.
+    #[clap(long, short('j'), default_value("1"), validator(jobs_validator))]
+    jobs: usize,
+
+    /// Keeps running after the deployment, and rebuilds and upgrades the canisters whose
+    /// source files change. Only available on local networks.
+    #[clap(long)]
+    watch: bool,
+
+    /// Upgrades the canisters even if their new interfaces break the clients of the installed ones.
+    #[clap(long)]
+    allow_breaking: bool,
+
     /// Specifies the initial cycle balance to deposit into the newly created canister.
     /// The specified amount needs to take the canister create fee into account.
     /// This amount is deducted from the wallet's cycle balance.
@@ -58,6 +81,10 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
     let argument_type = opts.argument_type.as_deref();
     let with_cycles = opts.with_cycles.as_deref();
 
+    if opts.watch && env.get_network_descriptor().map_or(false, |n| n.is_ic) {
+        bail!("The --watch option is only available on local networks.");
+    }
+
     let runtime = Runtime::new().expect("Unable to create a runtime");
 
     let default_wallet_proxy = true;
@@ -76,6 +103,26 @@ pub fn exec(env: &dyn Environment, opts: DeployOpts) -> DfxResult {
         argument_type,
         timeout,
         with_cycles,
+        opts.profile.as_deref(),
+        opts.force,
+        opts.jobs,
+        opts.allow_breaking,
         &call_sender,
-    ))
+    ))?;
+
+    if opts.watch {
+        watch_deployed_canisters(
+            &env,
+            &runtime,
+            canister_name,
+            argument,
+            argument_type,
+            timeout,
+            opts.profile.as_deref(),
+            opts.jobs,
+            opts.allow_breaking,
+            &call_sender,
+        )?;
+    }
+    Ok(())
 }
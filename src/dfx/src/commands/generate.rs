@@ -0,0 +1,92 @@
+use crate::lib::builders::BuildConfig;
+use crate::lib::declarations::generate_declarations;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::models::canister::CanisterPool;
+use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::provider::{create_agent_environment, get_network_context};
+
+use clap::Clap;
+use slog::info;
+
+/// Generates the client bindings of canisters (JavaScript, TypeScript, Candid, Motoko or
+/// Rust), as configured by their "declarations" in dfx.json. By default, the bindings of
+/// all canisters are generated.
+#[derive(Clap)]
+pub struct GenerateOpts {
+    /// Specifies the name of the canister to generate bindings for.
+    canister_name: Option<String>,
+
+    /// Specifies the build profile: Debug, Release or the name of a profile defined in dfx.json.
+    /// By default, the profile set in dfx.json is used.
+    #[clap(long)]
+    profile: Option<String>,
+
+    /// Override the compute network to connect to. By default, the local network is used.
+    /// The actor factories use the canister IDs on this network.
+    #[clap(long)]
+    network: Option<String>,
+
+    /// Build the canisters that were not created on the network yet with temporary IDs, in
+    /// a separate build directory that leaves the build outputs of the project untouched.
+    #[clap(long)]
+    check: bool,
+}
+
+pub fn exec(env: &dyn Environment, opts: GenerateOpts) -> DfxResult {
+    let env = create_agent_environment(env, opts.network)?;
+    let log = env.get_logger();
+    let config = env.get_config_or_anyhow()?;
+    env.get_cache().install()?;
+
+    let canister_names = config
+        .get_config()
+        .get_canister_names_with_dependencies(opts.canister_name.as_deref())?;
+
+    let canister_id_store = CanisterIdStore::for_env(&env)?;
+    let build_config = BuildConfig::from_config(&config, opts.profile.as_deref())?;
+    let canister_pool = if opts.check {
+        // The bindings only depend on the interfaces, so canisters that were not created on
+        // this network yet are built with temporary IDs.
+        let build_root = config
+            .get_temp_path()
+            .join(get_network_context()?)
+            .join("check");
+        std::fs::create_dir_all(&build_root)?;
+        let canister_pool =
+            CanisterPool::load_with_build_root(&env, true, &canister_names, Some(&build_root))?;
+        canister_pool.build_or_fail(
+            build_config
+                .with_build_root(&build_root)
+                .with_build_mode_check(true),
+        )?;
+        canister_pool
+    } else {
+        for name in &canister_names {
+            canister_id_store.get(name)?;
+        }
+        let canister_pool = CanisterPool::load(&env, false, &canister_names)?;
+        canister_pool.build_or_fail(build_config)?;
+        canister_pool
+    };
+
+    let is_ic = env.get_network_descriptor().map_or(false, |n| n.is_ic);
+    for canister in canister_pool.get_canister_list() {
+        let name = canister.get_name();
+        if opts.canister_name.as_deref().map_or(false, |n| n != name) {
+            continue;
+        }
+        let info = canister_pool
+            .get_canister_info(&canister.canister_id())
+            .unwrap();
+        let canister_id = canister_id_store.find(name);
+        let output = generate_declarations(info, canister_id.as_ref(), is_ic)?;
+        info!(
+            log,
+            "Generated the declarations of canister {} in '{}'.",
+            name,
+            output.display()
+        );
+    }
+    Ok(())
+}
@@ -28,7 +28,7 @@ pub struct ReplicaOpts {
 
 /// Gets the configuration options for the Internet Computer replica.
 fn get_config(env: &dyn Environment, opts: ReplicaOpts) -> DfxResult<ReplicaConfig> {
-    let config = get_config_from_file(env);
+    let config = get_config_from_file(env)?;
     let port = get_port(&config, opts.port)?;
     let mut http_handler: HttpHandlerConfig = Default::default();
     if port == 0 {
@@ -48,9 +48,10 @@ fn get_config(env: &dyn Environment, opts: ReplicaOpts) -> DfxResult<ReplicaConf
 
 /// Gets the configuration options for the Internet Computer replica as they were specified in the
 /// dfx configuration file.
-fn get_config_from_file(env: &dyn Environment) -> ConfigDefaultsReplica {
-    env.get_config().map_or(Default::default(), |config| {
-        config.get_config().get_defaults().get_replica().to_owned()
+fn get_config_from_file(env: &dyn Environment) -> DfxResult<ConfigDefaultsReplica> {
+    env.get_config().map_or(Ok(Default::default()), |config| {
+        config.get_config().check_resolved("defaults.replica")?;
+        Ok(config.get_config().get_defaults().get_replica().to_owned())
     })
 }
 
@@ -14,7 +14,7 @@ use std::process::ExitStatus;
 // POSIX permissions for files in the cache.
 const EXEC_READ_USER_ONLY_PERMISSION: u32 = 0o500;
 
-pub trait Cache {
+pub trait Cache: Send + Sync {
     fn version_str(&self) -> String;
     fn is_installed(&self) -> DfxResult<bool>;
     fn install(&self) -> DfxResult;
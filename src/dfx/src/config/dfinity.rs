@@ -1,5 +1,9 @@
 #![allow(dead_code)]
+use crate::config::interpolation::{interpolate, Unresolved, Variables};
+use crate::config::migration::{config_version, CURRENT_CONFIG_VERSION};
+use crate::config::schema::{self, ConfigDiagnostic};
 use crate::lib::error::{BuildError, DfxError, DfxResult};
+use crate::lib::provider::get_network_context;
 use crate::{error_invalid_config, error_invalid_data};
 
 use anyhow::anyhow;
@@ -34,6 +38,8 @@ const EMPTY_CONFIG_DEFAULTS_REPLICA: ConfigDefaultsReplica = ConfigDefaultsRepli
 
 const DEFAULT_LOCAL_BIND: &str = "127.0.0.1:8000";
 pub const DEFAULT_IC_GATEWAY: &str = "https://ic0.app";
+/// The languages the client bindings of canisters can be generated in.
+pub const BINDINGS: &[&str] = &["js", "ts", "did", "mo", "rs"];
 
 /// A Canister configuration in the dfx.json config file.
 /// It only contains a type; everything else should be infered using the
@@ -42,8 +48,26 @@ pub const DEFAULT_IC_GATEWAY: &str = "https://ic0.app";
 pub struct ConfigCanistersCanister {
     pub r#type: Option<String>,
 
+    /// Overrides of the canister configuration, keyed by network name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub networks: BTreeMap<String, BTreeMap<String, Value>>,
+
     #[serde(flatten)]
     pub extras: BTreeMap<String, Value>,
+
+    /// The workspace member that declares this canister, if it is not declared in the root
+    /// configuration file.
+    #[serde(skip)]
+    pub member: Option<WorkspaceMember>,
+}
+
+/// A project included in a workspace.
+#[derive(Clone, Debug)]
+pub struct WorkspaceMember {
+    /// The name of the member, which prefixes the names of its canisters.
+    pub name: String,
+    /// The directory of the member, which contains its dfx.json.
+    pub root: PathBuf,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -123,6 +147,37 @@ pub enum Profile {
     Release,
 }
 
+/// A named build profile.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ConfigProfile {
+    /// The code generation of the profile. Defaults to Debug.
+    pub codegen: Option<Profile>,
+
+    /// Extra arguments passed to the compiler of each canister type, e.g. `moc` for
+    /// `motoko` canisters.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, Vec<String>>,
+
+    /// Environment variables set for the build commands of custom canisters.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+
+    /// A directory, relative to the project root, where the Wasm module and Candid
+    /// interface of every canister built are copied.
+    pub output: Option<String>,
+
+    /// Whether to remove the names and custom sections of the Wasm modules. Defaults to
+    /// true for the Release code generation.
+    pub strip: Option<bool>,
+
+    /// The custom sections kept in the Wasm modules when stripping them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keep_sections: Vec<String>,
+
+    /// The maximum size of a Wasm module, e.g. "2MB". Larger modules fail the build.
+    pub max_wasm_size: Option<String>,
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ConfigDefaults {
     pub bootstrap: Option<ConfigDefaultsBootstrap>,
@@ -130,17 +185,82 @@ pub struct ConfigDefaults {
     pub replica: Option<ConfigDefaultsReplica>,
 }
 
+/// The workspace section of a root configuration file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ConfigWorkspace {
+    /// The directories of the projects included in the workspace.
+    pub members: Vec<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ConfigInterface {
-    pub profile: Option<Profile>,
+    pub profile: Option<String>,
+    pub profiles: Option<BTreeMap<String, ConfigProfile>>,
     pub version: Option<u32>,
     pub dfx: Option<String>,
     pub canisters: Option<BTreeMap<String, ConfigCanistersCanister>>,
     pub defaults: Option<ConfigDefaults>,
     pub networks: Option<BTreeMap<String, ConfigNetwork>>,
+    pub workspace: Option<ConfigWorkspace>,
+
+    /// The variable references that could not be resolved. The values with them are kept
+    /// as written, and fail to be used.
+    #[serde(skip)]
+    pub unresolved: Unresolved,
 }
 
-impl ConfigCanistersCanister {}
+impl ConfigCanistersCanister {
+    /// Return this canister configuration with the overrides of a network applied.
+    /// Objects are merged key by key; any other value replaces the original one.
+    pub fn for_network(&self, network_name: &str) -> DfxResult<ConfigCanistersCanister> {
+        let mut canister = ConfigCanistersCanister {
+            r#type: self.r#type.clone(),
+            networks: BTreeMap::new(),
+            extras: self.extras.clone(),
+            member: self.member.clone(),
+        };
+
+        if let Some(overrides) = self.networks.get(network_name) {
+            for (key, value) in overrides {
+                if key == "type" {
+                    let canister_type = String::deserialize(value).map_err(|_| {
+                        error_invalid_config!(
+                            "Field 'networks.{}.type' is of the wrong type",
+                            network_name
+                        )
+                    })?;
+                    canister.r#type = Some(canister_type);
+                } else if value.is_null() {
+                    canister.extras.remove(key);
+                } else {
+                    merge_value(
+                        canister.extras.entry(key.clone()).or_insert(Value::Null),
+                        value,
+                    );
+                }
+            }
+        }
+
+        Ok(canister)
+    }
+}
+
+/// Merge an override into a value. Objects are merged key by key, and a `null` removes the
+/// key it is set on.
+fn merge_value(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                if value.is_null() {
+                    base.remove(key);
+                } else {
+                    merge_value(base.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
 
 pub fn to_socket_addr(s: &str) -> DfxResult<SocketAddr> {
     match s.to_socket_addrs() {
@@ -186,6 +306,12 @@ impl ConfigDefaults {
 }
 
 impl ConfigInterface {
+    /// Fails if a value at or under a path of the configuration, e.g. `networks.ic`, has a
+    /// variable that could not be resolved.
+    pub fn check_resolved(&self, path: &str) -> DfxResult {
+        Ok(self.unresolved.check(path, &[])?)
+    }
+
     pub fn get_defaults(&self) -> &ConfigDefaults {
         match &self.defaults {
             Some(v) => &v,
@@ -193,6 +319,7 @@ impl ConfigInterface {
         }
     }
     pub fn get_provider_url(&self, network: &str) -> DfxResult<Option<String>> {
+        self.check_resolved(&format!("networks.{}", network))?;
         match &self.networks {
             Some(networks) => match networks.get(network) {
                 Some(ConfigNetwork::ConfigNetworkProvider(network_provider)) => {
@@ -231,6 +358,7 @@ impl ConfigInterface {
     }
 
     pub fn get_local_bind_address(&self, default: &str) -> DfxResult<SocketAddr> {
+        self.check_resolved("networks.local")?;
         self.get_network("local")
             .map(|network| match network {
                 ConfigNetwork::ConfigLocalProvider(local) => to_socket_addr(&local.bind),
@@ -248,6 +376,69 @@ impl ConfigInterface {
         self.dfx.to_owned()
     }
 
+    /// Return the build profile with the given name, or the default profile of the project
+    /// if no name is given. `Debug` and `Release` are always available, unless the project
+    /// defines profiles with the same name.
+    pub fn get_profile(&self, name: Option<&str>) -> DfxResult<(String, ConfigProfile)> {
+        if name.is_none() {
+            self.check_resolved("profile")?;
+        }
+        let name = name.or_else(|| self.profile.as_deref()).unwrap_or("Debug");
+        if let Some(profile) = self.profiles.as_ref().and_then(|p| p.get(name)) {
+            self.check_resolved(&format!("profiles.{}", name))?;
+            return Ok((name.to_string(), profile.clone()));
+        }
+
+        let codegen = match name {
+            "Debug" | "debug" => Profile::Debug,
+            "Release" | "release" => Profile::Release,
+            _ => {
+                let mut names = vec!["Debug".to_string(), "Release".to_string()];
+                names.extend(self.profiles.iter().flat_map(|p| p.keys().cloned()));
+                return Err(anyhow!(
+                    "Cannot find build profile '{}'. Available profiles: {}.",
+                    name,
+                    names.join(", ")
+                ));
+            }
+        };
+        Ok((
+            name.to_string(),
+            ConfigProfile {
+                codegen: Some(codegen),
+                ..ConfigProfile::default()
+            },
+        ))
+    }
+
+    /// Return the configuration of a canister, with the overrides of the current network
+    /// applied. If no network was selected, the configuration is returned as is.
+    pub fn get_canister_config(&self, canister_name: &str) -> DfxResult<ConfigCanistersCanister> {
+        let canister_map = (&self.canisters)
+            .as_ref()
+            .ok_or_else(|| error_invalid_config!("No canisters in the configuration file."))?;
+
+        let canister_config = canister_map
+            .get(canister_name)
+            .ok_or_else(|| anyhow!("Cannot find canister '{}'.", canister_name))?;
+
+        // Only the overrides of the current network are used.
+        let network_name = get_network_context().ok();
+        let path = format!("canisters.{}", canister_name);
+        let other_networks: Vec<String> = canister_config
+            .networks
+            .keys()
+            .filter(|name| Some(*name) != network_name.as_ref())
+            .map(|name| format!("{}.networks.{}", path, name))
+            .collect();
+        self.unresolved.check(&path, &other_networks)?;
+
+        match network_name {
+            Some(network_name) => canister_config.for_network(&network_name),
+            None => Ok(canister_config.clone()),
+        }
+    }
+
     /// Return the names of the specified canister and all of its dependencies.
     /// If none specified, return the names of all canisters.
     pub fn get_canister_names_with_dependencies(
@@ -262,7 +453,7 @@ impl ConfigInterface {
             Some(specific_canister) => {
                 let mut names = HashSet::new();
                 let mut path = vec![];
-                add_dependencies(canister_map, &mut names, &mut path, specific_canister)?;
+                add_dependencies(self, &mut names, &mut path, specific_canister)?;
                 names.into_iter().collect()
             }
             None => canister_map.keys().cloned().collect(),
@@ -288,13 +479,7 @@ impl ConfigInterface {
         canister_name: &str,
         field: &str,
     ) -> DfxResult<Option<String>> {
-        let canister_map = (&self.canisters)
-            .as_ref()
-            .ok_or_else(|| error_invalid_config!("No canisters in the configuration file."))?;
-
-        let canister_config = canister_map
-            .get(canister_name)
-            .ok_or_else(|| anyhow!("Cannot find canister '{}'.", canister_name))?;
+        let canister_config = self.get_canister_config(canister_name)?;
 
         canister_config
             .extras
@@ -307,7 +492,7 @@ impl ConfigInterface {
 }
 
 fn add_dependencies(
-    all_canisters: &BTreeMap<String, ConfigCanistersCanister>,
+    config: &ConfigInterface,
     names: &mut HashSet<String>,
     path: &mut Vec<String>,
     canister_name: &str,
@@ -326,9 +511,7 @@ fn add_dependencies(
         };
     }
 
-    let canister_config = all_canisters
-        .get(canister_name)
-        .ok_or_else(|| anyhow!("Cannot find canister '{}'.", canister_name))?;
+    let canister_config = config.get_canister_config(canister_name)?;
 
     let deps = match canister_config.extras.get("dependencies") {
         None => vec![],
@@ -339,7 +522,7 @@ fn add_dependencies(
     path.push(String::from(canister_name));
 
     for canister in deps {
-        add_dependencies(all_canisters, names, path, &canister)?;
+        add_dependencies(config, names, path, &canister)?;
     }
 
     path.pop();
@@ -350,9 +533,14 @@ fn add_dependencies(
 #[derive(Clone)]
 pub struct Config {
     path: PathBuf,
+    /// The content of the file, as written.
     json: Value,
+    /// The content of the file, with variables resolved.
+    resolved_json: Value,
     // public interface to the config:
     pub config: ConfigInterface,
+    /// Problems found when validating the file against the dfx.json schema.
+    diagnostics: Vec<ConfigDiagnostic>,
 }
 
 #[allow(dead_code)]
@@ -383,19 +571,88 @@ impl Config {
         Config::from_slice(path.to_path_buf(), &content)
     }
 
+    /// Return the path of the configuration file of the workspace that includes the project
+    /// of a configuration file, if there is one.
+    pub fn resolve_workspace_path(config_path: &Path) -> Option<PathBuf> {
+        let project_root = config_path.parent()?;
+        project_root
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .filter(|path| path.is_file())
+            .find(|path| {
+                let workspace_root = path.parent().unwrap();
+                std::fs::read(path)
+                    .ok()
+                    .and_then(|content| serde_json::from_slice::<Value>(&content).ok())
+                    .and_then(|json| json.pointer("/workspace/members").cloned())
+                    .and_then(|members| serde_json::from_value::<Vec<String>>(members).ok())
+                    .map_or(false, |members| {
+                        members.iter().any(|member| {
+                            workspace_root.join(member).canonicalize().ok().as_deref()
+                                == Some(project_root)
+                        })
+                    })
+            })
+    }
+
+    /// Load the configuration of the project in a directory. If the project is a member of
+    /// a workspace, the configuration of the whole workspace is loaded.
     pub fn from_dir(working_dir: &Path) -> std::io::Result<Config> {
         let path = Config::resolve_config_path(working_dir)?;
+        let path = Config::resolve_workspace_path(&path).unwrap_or(path);
         Config::from_file(&path)
     }
 
+    /// Load the configuration file nearest to a directory, even if its project is a member
+    /// of a workspace. This is the file that `dfx config` reads and writes.
+    pub fn from_nearest_file(working_dir: &Path) -> std::io::Result<Config> {
+        Config::from_file(&Config::resolve_config_path(working_dir)?)
+    }
+
     pub fn from_current_dir() -> std::io::Result<Config> {
         Config::from_dir(&std::env::current_dir()?)
     }
 
     fn from_slice(path: PathBuf, content: &[u8]) -> std::io::Result<Config> {
-        let config = serde_json::from_slice(&content)?;
-        let json = serde_json::from_slice(&content)?;
-        Ok(Config { path, json, config })
+        let json: Value = serde_json::from_slice(&content)?;
+        let diagnostics = schema::validate(
+            &schema::dfx_json_schema(),
+            &json,
+            Some(&String::from_utf8_lossy(content)),
+        );
+
+        // A path without parent (e.g. a config from a string) has no .env file.
+        let variables = match path.parent() {
+            Some(root) if !root.as_os_str().is_empty() => Variables::load(root)?,
+            _ => Variables::default(),
+        };
+        let (resolved_json, unresolved) = interpolate(&json, &variables);
+
+        let mut config = ConfigInterface::deserialize(&resolved_json).map_err(|err| {
+            if diagnostics.is_empty() {
+                std::io::Error::from(err)
+            } else {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "{}\n{}",
+                        err,
+                        format_diagnostics(&path, &diagnostics).join("\n")
+                    ),
+                )
+            }
+        })?;
+        config.unresolved = unresolved;
+        add_workspace_members(&path, &mut config)?;
+
+        Ok(Config {
+            path,
+            json,
+            resolved_json,
+            config,
+            diagnostics,
+        })
     }
 
     /// Create a configuration from a string.
@@ -417,12 +674,27 @@ impl Config {
     pub fn get_json(&self) -> &Value {
         &self.json
     }
+    pub fn get_resolved_json(&self) -> &Value {
+        &self.resolved_json
+    }
     pub fn get_mut_json(&mut self) -> &mut Value {
         &mut self.json
     }
     pub fn get_config(&self) -> &ConfigInterface {
         &self.config
     }
+    pub fn get_diagnostics(&self) -> &[ConfigDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the version of the configuration layout of the file if it is older than the
+    /// one used by this version of dfx, i.e. if `dfx config migrate` would change it.
+    pub fn get_outdated_version(&self) -> Option<u32> {
+        match config_version(&self.json) {
+            Ok(version) if version < CURRENT_CONFIG_VERSION => Some(version),
+            _ => None,
+        }
+    }
 
     pub fn get_project_root(&self) -> &Path {
         // a configuration path contains a file name specifically. As
@@ -441,6 +713,105 @@ impl Config {
     }
 }
 
+/// Add the canisters of the members of a workspace to its configuration. Their names are
+/// prefixed with the name of the member (the last component of its directory), e.g.
+/// `backend.api`, and so are the dependencies between canisters of the same member.
+fn add_workspace_members(path: &Path, config: &mut ConfigInterface) -> std::io::Result<()> {
+    let members = match &config.workspace {
+        Some(workspace) => workspace.members.clone(),
+        None => return Ok(()),
+    };
+    let invalid_data =
+        |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+    let workspace_root = path.parent().unwrap_or_else(|| Path::new("."));
+    let canisters = config.canisters.get_or_insert_with(BTreeMap::new);
+    let mut member_names = HashSet::new();
+    for member_dir in members {
+        let member_name = Path::new(&member_dir)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| invalid_data(format!("Invalid workspace member '{}'.", member_dir)))?
+            .to_string();
+        if !member_names.insert(member_name.clone()) {
+            return Err(invalid_data(format!(
+                "Several workspace members are named '{}'.",
+                member_name
+            )));
+        }
+
+        let member_root = workspace_root.join(&member_dir);
+        let member_config =
+            Config::from_file(&member_root.join(CONFIG_FILE_NAME)).map_err(|err| {
+                std::io::Error::new(
+                    err.kind(),
+                    format!("Cannot load workspace member '{}': {}", member_dir, err),
+                )
+            })?;
+        if member_config.config.workspace.is_some() {
+            return Err(invalid_data(format!(
+                "Workspace member '{}' cannot be a workspace itself.",
+                member_dir
+            )));
+        }
+
+        // Only the canisters of the members are part of the workspace.
+        config
+            .unresolved
+            .extend_moved(member_config.config.unresolved, |path| {
+                path.strip_prefix("canisters.")
+                    .map(|path| format!("canisters.{}.{}", member_name, path))
+            });
+        let member_canisters = member_config.config.canisters.unwrap_or_default();
+        let local_names: HashSet<String> = member_canisters.keys().cloned().collect();
+        for (canister_name, mut canister) in member_canisters {
+            qualify_dependencies(&mut canister.extras, &member_name, &local_names);
+            for overrides in canister.networks.values_mut() {
+                qualify_dependencies(overrides, &member_name, &local_names);
+            }
+            canister.member = Some(WorkspaceMember {
+                name: member_name.clone(),
+                root: member_root.clone(),
+            });
+
+            let qualified_name = format!("{}.{}", member_name, canister_name);
+            if canisters.insert(qualified_name.clone(), canister).is_some() {
+                return Err(invalid_data(format!(
+                    "Canister '{}' is declared more than once in the workspace.",
+                    qualified_name
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prefix the dependencies on canisters of the same member with the name of the member.
+/// Other dependencies already use the names of the workspace.
+fn qualify_dependencies(
+    fields: &mut BTreeMap<String, Value>,
+    member_name: &str,
+    local_names: &HashSet<String>,
+) {
+    if let Some(Value::Array(dependencies)) = fields.get_mut("dependencies") {
+        for dependency in dependencies.iter_mut() {
+            if let Value::String(name) = dependency {
+                if local_names.contains(name.as_str()) {
+                    *name = format!("{}.{}", member_name, name);
+                }
+            }
+        }
+    }
+}
+
+/// Format validation diagnostics as `path:line:column: message` lines.
+pub fn format_diagnostics(path: &Path, diagnostics: &[ConfigDiagnostic]) -> Vec<String> {
+    diagnostics
+        .iter()
+        .map(|d| format!("{}:{}", path.display(), d))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -690,4 +1061,199 @@ mod tests {
         assert_eq!(None, compute_allocation);
         assert_eq!(None, memory_allocation);
     }
+
+    #[test]
+    fn canister_network_overrides_are_merged() {
+        let config = Config::from_str(
+            r#"{
+              "canisters": {
+                "ledger": {
+                  "type": "custom",
+                  "wasm": "ledger.wasm",
+                  "candid": "ledger.did",
+                  "initialization_values": {
+                    "compute_allocation": "10",
+                    "memory_allocation": "1GB"
+                  },
+                  "networks": {
+                    "local": {
+                      "type": "motoko",
+                      "main": "mock/ledger.mo",
+                      "initialization_values": {
+                        "memory_allocation": "8GB"
+                      }
+                    }
+                  }
+                }
+              }
+        }"#,
+        )
+        .unwrap();
+
+        let canister = &config.get_config().canisters.as_ref().unwrap()["ledger"];
+
+        let ic = canister.for_network("ic").unwrap();
+        assert_eq!(ic.r#type.as_deref(), Some("custom"));
+        assert!(ic.extras.get("main").is_none());
+
+        let local = canister.for_network("local").unwrap();
+        assert_eq!(local.r#type.as_deref(), Some("motoko"));
+        assert_eq!(local.extras["main"], "mock/ledger.mo");
+        assert_eq!(local.extras["wasm"], "ledger.wasm");
+        assert_eq!(
+            local.extras["initialization_values"],
+            serde_json::json!({ "compute_allocation": "10", "memory_allocation": "8GB" })
+        );
+        assert!(local.networks.is_empty());
+    }
+
+    #[test]
+    fn unresolved_variables_only_fail_where_used() {
+        let config = Config::from_str(
+            r#"{
+              "canisters": {
+                "app": {
+                  "main": "app.mo",
+                  "networks": { "staging": { "main": "${DFX_TEST_UNSET_MAIN}" } }
+                }
+              },
+              "networks": {
+                "staging": { "providers": [ "${DFX_TEST_UNSET_PROVIDER}" ] }
+              }
+        }"#,
+        )
+        .unwrap();
+        let config_interface = config.get_config();
+
+        assert_eq!(
+            config.get_json().pointer("/networks/staging/providers/0"),
+            config
+                .get_resolved_json()
+                .pointer("/networks/staging/providers/0")
+        );
+        assert!(config_interface.get_provider_url("ic").is_ok());
+        let err = config_interface.get_provider_url("staging").unwrap_err();
+        assert!(err.to_string().contains("DFX_TEST_UNSET_PROVIDER"));
+
+        let app = config_interface.get_canister_config("app").unwrap();
+        assert_eq!(app.extras["main"], "app.mo");
+        assert!(config_interface.check_resolved("canisters.app").is_err());
+        assert!(config_interface.check_resolved("defaults").is_ok());
+    }
+
+    #[test]
+    fn null_canister_network_overrides_remove_fields() {
+        let config = Config::from_str(
+            r#"{
+              "canisters": {
+                "ledger": {
+                  "main": "ledger.mo",
+                  "dependencies": [ "archive" ],
+                  "initialization_values": {
+                    "compute_allocation": "10",
+                    "memory_allocation": "1GB"
+                  },
+                  "networks": {
+                    "local": {
+                      "dependencies": null,
+                      "initialization_values": { "memory_allocation": null }
+                    }
+                  }
+                }
+              }
+        }"#,
+        )
+        .unwrap();
+
+        let canister = &config.get_config().canisters.as_ref().unwrap()["ledger"];
+        let local = canister.for_network("local").unwrap();
+        assert!(!local.extras.contains_key("dependencies"));
+        assert_eq!(
+            local.extras["initialization_values"],
+            serde_json::json!({ "compute_allocation": "10" })
+        );
+        assert!(canister
+            .for_network("ic")
+            .unwrap()
+            .extras
+            .contains_key("dependencies"));
+    }
+
+    #[test]
+    fn get_named_and_builtin_profiles() {
+        let config = Config::from_str(
+            r#"{
+              "profile": "ci",
+              "profiles": {
+                "ci": { "args": { "motoko": [ "-Werror" ] } },
+                "staging": { "codegen": "Release", "env": { "API": "staging" }, "output": "dist" }
+              }
+            }"#,
+        )
+        .unwrap();
+        let config_interface = config.get_config();
+
+        let (name, profile) = config_interface.get_profile(None).unwrap();
+        assert_eq!(name, "ci");
+        assert_eq!(profile.args["motoko"], vec!["-Werror"]);
+        assert!(profile.codegen.is_none());
+
+        let (_, profile) = config_interface.get_profile(Some("staging")).unwrap();
+        assert!(matches!(profile.codegen, Some(Profile::Release)));
+        assert_eq!(profile.env["API"], "staging");
+        assert_eq!(profile.output.as_deref(), Some("dist"));
+
+        let (_, profile) = config_interface.get_profile(Some("release")).unwrap();
+        assert!(matches!(profile.codegen, Some(Profile::Release)));
+
+        assert!(config_interface.get_profile(Some("prod")).is_err());
+    }
+
+    #[test]
+    fn workspace_members_are_namespaced() {
+        let root_dir = tempfile::tempdir().unwrap();
+        let root_path = root_dir.into_path().canonicalize().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = root_path.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            CONFIG_FILE_NAME,
+            r#"{ "workspace": { "members": [ "services/backend", "frontend" ] } }"#,
+        );
+        write(
+            "services/backend/dfx.json",
+            r#"{ "canisters": {
+                  "api": { "main": "api.mo", "dependencies": [ "store" ] },
+                  "store": { "main": "store.mo" }
+            } }"#,
+        );
+        write(
+            "frontend/dfx.json",
+            r#"{ "canisters": {
+                  "app": { "type": "assets", "source": [ "dist" ], "dependencies": [ "backend.api" ] }
+            } }"#,
+        );
+
+        // Loading from a member directory loads the whole workspace.
+        let config = Config::from_dir(&root_path.join("frontend")).unwrap();
+        assert_eq!(config.get_path(), &root_path.join(CONFIG_FILE_NAME));
+
+        let interface = config.get_config();
+        let mut names = interface
+            .get_canister_names_with_dependencies(None)
+            .unwrap();
+        names.sort();
+        assert_eq!(names, vec!["backend.api", "backend.store", "frontend.app"]);
+
+        let mut names = interface
+            .get_canister_names_with_dependencies(Some("frontend.app"))
+            .unwrap();
+        names.sort();
+        assert_eq!(names, vec!["backend.api", "backend.store", "frontend.app"]);
+
+        let api = interface.get_canister_config("backend.api").unwrap();
+        assert_eq!(api.member.unwrap().root, root_path.join("services/backend"));
+    }
 }
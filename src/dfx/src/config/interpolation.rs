@@ -0,0 +1,302 @@
+//! Interpolation of environment variables in the string values of dfx.json.
+//!
+//! `${NAME}` is replaced by the value of the variable `NAME`, and `${NAME:-default}` by
+//! `default` if the variable is unset or empty. `$${` is replaced by a literal `${`.
+//! Variables are looked up in the environment first, then in a `.env` file next to dfx.json.
+//!
+//! A value with a reference that cannot be resolved keeps its text as written, so the file
+//! still loads; using that value fails with an error naming the variable.
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+pub const DOTENV_FILE_NAME: &str = ".env";
+
+#[derive(Clone, Error, Debug)]
+pub enum InterpolationError {
+    #[error("Unresolved variable '{name}' in '{path}'. Set it in the environment or in .env, or give it a default value with ${{{name}:-default}}.")]
+    UnresolvedVariable { name: String, path: String },
+
+    #[error("Unterminated variable reference in '{path}': missing '}}'.")]
+    UnterminatedReference { path: String },
+}
+
+impl InterpolationError {
+    /// The path of the value with the reference, e.g. `networks.ic.providers.0`.
+    pub fn path(&self) -> &str {
+        match self {
+            InterpolationError::UnresolvedVariable { path, .. } => path,
+            InterpolationError::UnterminatedReference { path } => path,
+        }
+    }
+
+    fn with_path(self, path: String) -> Self {
+        match self {
+            InterpolationError::UnresolvedVariable { name, .. } => {
+                InterpolationError::UnresolvedVariable { name, path }
+            }
+            InterpolationError::UnterminatedReference { .. } => {
+                InterpolationError::UnterminatedReference { path }
+            }
+        }
+    }
+}
+
+/// The references of a configuration that could not be resolved.
+#[derive(Clone, Debug, Default)]
+pub struct Unresolved(Vec<InterpolationError>);
+
+impl Unresolved {
+    /// Fails if a value at or under a path (e.g. `networks.ic`, or `` for the whole file)
+    /// has a reference that could not be resolved, unless it is under one of the `except`
+    /// paths.
+    pub fn check(&self, path: &str, except: &[String]) -> Result<(), InterpolationError> {
+        let is_under = |path: &str, prefix: &str| {
+            prefix.is_empty()
+                || path == prefix
+                || (path.starts_with(prefix) && path[prefix.len()..].starts_with('.'))
+        };
+        match self.0.iter().find(|error| {
+            is_under(error.path(), path)
+                && !except.iter().any(|except| is_under(error.path(), except))
+        }) {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Add the references of another configuration whose values were moved under other
+    /// paths. `move_path` returns the new path of a value, or None if it was not kept.
+    pub fn extend_moved(&mut self, other: Unresolved, move_path: impl Fn(&str) -> Option<String>) {
+        for error in other.0 {
+            if let Some(path) = move_path(error.path()) {
+                self.0.push(error.with_path(path));
+            }
+        }
+    }
+}
+
+/// The variables available to dfx.json.
+#[derive(Default)]
+pub struct Variables {
+    dotenv: BTreeMap<String, String>,
+}
+
+impl Variables {
+    /// Load the variables of the `.env` file in the project root, if there is one.
+    pub fn load(project_root: &Path) -> std::io::Result<Self> {
+        let path = project_root.join(DOTENV_FILE_NAME);
+        let dotenv = if path.is_file() {
+            parse_dotenv(&std::fs::read_to_string(&path)?)
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Variables { dotenv })
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        std::env::var(name)
+            .ok()
+            .or_else(|| self.dotenv.get(name).cloned())
+    }
+}
+
+/// Parse the content of a `.env` file. Each line is a `NAME=value` pair, optionally
+/// prefixed with `export` and with the value between quotes. Empty lines and lines
+/// starting with `#` are ignored.
+fn parse_dotenv(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_at(line.find('=')?);
+            let value = value[1..].trim();
+            let value = if value.len() >= 2
+                && ((value.starts_with('"') && value.ends_with('"'))
+                    || (value.starts_with('\'') && value.ends_with('\'')))
+            {
+                &value[1..value.len() - 1]
+            } else {
+                value
+            };
+            Some((name.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Return a copy of the JSON value with the variables of every string value resolved, and
+/// the references that could not be resolved. Their values are left as written.
+pub fn interpolate(json: &Value, variables: &Variables) -> (Value, Unresolved) {
+    let mut json = json.clone();
+    let mut unresolved = Unresolved::default();
+    interpolate_value(&mut json, &mut vec![], variables, &mut unresolved);
+    (json, unresolved)
+}
+
+fn interpolate_value(
+    value: &mut Value,
+    path: &mut Vec<String>,
+    variables: &Variables,
+    unresolved: &mut Unresolved,
+) {
+    match value {
+        Value::String(s) => {
+            if s.contains('$') {
+                match interpolate_str(s, &path.join("."), variables) {
+                    Ok(resolved) => *s = resolved,
+                    Err(error) => unresolved.0.push(error),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                path.push(i.to_string());
+                interpolate_value(item, path, variables, unresolved);
+                path.pop();
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                path.push(key.clone());
+                interpolate_value(item, path, variables, unresolved);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn interpolate_str(
+    s: &str,
+    path: &str,
+    variables: &Variables,
+) -> Result<String, InterpolationError> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        if after.starts_with("${") {
+            // An escaped reference.
+            result.push_str("${");
+            rest = &after[2..];
+        } else if let Some(reference) = after.strip_prefix('{') {
+            let end =
+                reference
+                    .find('}')
+                    .ok_or_else(|| InterpolationError::UnterminatedReference {
+                        path: path.to_string(),
+                    })?;
+            let (name, default) = match reference[..end].find(":-") {
+                Some(i) => (&reference[..i], Some(&reference[i + 2..end])),
+                None => (&reference[..end], None),
+            };
+            let value = match variables.get(name) {
+                Some(value) if !(value.is_empty() && default.is_some()) => value,
+                _ => match default {
+                    Some(default) => default.to_string(),
+                    None => {
+                        return Err(InterpolationError::UnresolvedVariable {
+                            name: name.to_string(),
+                            path: path.to_string(),
+                        })
+                    }
+                },
+            };
+            result.push_str(&value);
+            rest = &reference[end + 1..];
+        } else {
+            result.push('$');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(content: &str) -> Variables {
+        Variables {
+            dotenv: parse_dotenv(content),
+        }
+    }
+
+    #[test]
+    fn parse_dotenv_file() {
+        let dotenv = parse_dotenv(
+            "# A comment\n\
+             DFX_TEST_A=1\n\
+             export DFX_TEST_B = \"two words\"\n\
+             \n\
+             DFX_TEST_C='x=y'\n\
+             not a variable\n",
+        );
+
+        assert_eq!(dotenv.len(), 3);
+        assert_eq!(dotenv["DFX_TEST_A"], "1");
+        assert_eq!(dotenv["DFX_TEST_B"], "two words");
+        assert_eq!(dotenv["DFX_TEST_C"], "x=y");
+    }
+
+    #[test]
+    fn interpolate_variables_and_defaults() {
+        let variables = variables("DFX_TEST_HOST=1.2.3.4\nDFX_TEST_EMPTY=");
+
+        assert_eq!(
+            interpolate_str(
+                "https://${DFX_TEST_HOST}:${DFX_TEST_PORT:-8000}",
+                "p",
+                &variables
+            )
+            .unwrap(),
+            "https://1.2.3.4:8000"
+        );
+        assert_eq!(
+            interpolate_str("${DFX_TEST_EMPTY:-fallback}", "p", &variables).unwrap(),
+            "fallback"
+        );
+        assert_eq!(
+            interpolate_str("echo $${HOME} $PATH", "p", &variables).unwrap(),
+            "echo ${HOME} $PATH"
+        );
+    }
+
+    #[test]
+    fn unresolved_variable_is_named() {
+        let json = serde_json::json!({
+            "networks": {
+                "ic": { "providers": [ "${DFX_TEST_UNSET_PROVIDER}" ] },
+                "local": { "bind": "127.0.0.1:8000" }
+            }
+        });
+
+        let (resolved, unresolved) = interpolate(&json, &Variables::default());
+        assert_eq!(resolved, json);
+        assert!(unresolved.check("networks.local", &[]).is_ok());
+        assert!(unresolved.check("networks.i", &[]).is_ok());
+        assert!(unresolved
+            .check("networks", &["networks.ic".to_string()])
+            .is_ok());
+        assert!(unresolved.check("", &[]).is_err());
+        let err = unresolved.check("networks.ic", &[]).unwrap_err();
+        if let InterpolationError::UnresolvedVariable { name, path } = err {
+            assert_eq!(name, "DFX_TEST_UNSET_PROVIDER");
+            assert_eq!(path, "networks.ic.providers.0");
+        } else {
+            panic!("unexpected error");
+        }
+
+        assert!(interpolate_str("${DFX_TEST_HOST", "p", &Variables::default()).is_err());
+    }
+}
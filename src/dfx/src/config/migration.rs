@@ -0,0 +1,200 @@
+//! Migrations of dfx.json between versions of the configuration layout.
+//!
+//! The `version` field of dfx.json (1 if absent) names the layout the file was written
+//! for. Every change to the meaning of the configuration adds a migration from the
+//! previous version and bumps `CURRENT_CONFIG_VERSION`.
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// The version of the configuration layout used by this version of dfx.
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("The configuration version {version} is newer than the latest version known to this dfx ({current}). Use a newer version of dfx.")]
+    UnknownVersion { version: u32, current: u32 },
+
+    #[error("The configuration version must be a positive integer, found '{0}'.")]
+    InvalidVersion(Value),
+}
+
+/// A migration from one version of the configuration layout to the next.
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut Map<String, Value>),
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "Set the type of networks that do not specify one.",
+        apply: make_network_types_explicit,
+    },
+    Migration {
+        from: 1,
+        description: "Write canister initialization values as strings.",
+        apply: stringify_initialization_values,
+    },
+    Migration {
+        from: 1,
+        description: "Remove the unused defaults.build.output field.",
+        apply: remove_build_output,
+    },
+];
+
+/// Return the version of the configuration layout of a dfx.json file.
+pub fn config_version(json: &Value) -> Result<u32, MigrationError> {
+    match json.get("version") {
+        None | Some(Value::Null) => Ok(1),
+        Some(value) => value
+            .as_u64()
+            .filter(|v| *v >= 1 && *v <= u32::MAX as u64)
+            .map(|v| v as u32)
+            .ok_or_else(|| MigrationError::InvalidVersion(value.clone())),
+    }
+}
+
+/// Rewrite the content of a dfx.json file into the current layout. Returns the migrated
+/// content and the description of every change that was applied, in order.
+pub fn migrate(json: &Value) -> Result<(Value, Vec<&'static str>), MigrationError> {
+    let version = config_version(json)?;
+    if version > CURRENT_CONFIG_VERSION {
+        return Err(MigrationError::UnknownVersion {
+            version,
+            current: CURRENT_CONFIG_VERSION,
+        });
+    }
+
+    let mut json = json.clone();
+    let mut applied = vec![];
+    if let Some(map) = json.as_object_mut() {
+        for migration in MIGRATIONS.iter().filter(|m| m.from >= version) {
+            let before = map.clone();
+            (migration.apply)(map);
+            if *map != before {
+                applied.push(migration.description);
+            }
+        }
+        if version < CURRENT_CONFIG_VERSION {
+            map.insert("version".to_string(), Value::from(CURRENT_CONFIG_VERSION));
+        }
+    }
+    Ok((json, applied))
+}
+
+/// Networks defaulted to a type that depended on the version of dfx. Networks with a bind
+/// address are ephemeral and networks with providers are persistent.
+fn make_network_types_explicit(json: &mut Map<String, Value>) {
+    let networks = json.get_mut("networks").and_then(Value::as_object_mut);
+    for network in networks.into_iter().flat_map(|n| n.values_mut()) {
+        if let Some(network) = network.as_object_mut() {
+            if !network.contains_key("type") {
+                let network_type = if network.contains_key("providers") {
+                    "persistent"
+                } else {
+                    "ephemeral"
+                };
+                network.insert("type".to_string(), Value::from(network_type));
+            }
+        }
+    }
+}
+
+/// Initialization values written as numbers were rejected as being of the wrong type, while
+/// the same values written as strings are accepted.
+fn stringify_initialization_values(json: &mut Map<String, Value>) {
+    fn stringify(canister: &mut Value) {
+        let values = canister
+            .get_mut("initialization_values")
+            .and_then(Value::as_object_mut);
+        for value in values.into_iter().flat_map(|v| v.values_mut()) {
+            if value.is_number() {
+                *value = Value::String(value.to_string());
+            }
+        }
+    }
+
+    let canisters = json.get_mut("canisters").and_then(Value::as_object_mut);
+    for canister in canisters.into_iter().flat_map(|c| c.values_mut()) {
+        stringify(canister);
+        let networks = canister.get_mut("networks").and_then(Value::as_object_mut);
+        for overrides in networks.into_iter().flat_map(|n| n.values_mut()) {
+            stringify(overrides);
+        }
+    }
+}
+
+/// Canisters are always built in the .dfx directory.
+fn remove_build_output(json: &mut Map<String, Value>) {
+    let build = json
+        .get_mut("defaults")
+        .and_then(|defaults| defaults.get_mut("build"))
+        .and_then(Value::as_object_mut);
+    if let Some(build) = build {
+        build.remove("output");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn migrate_version_1() {
+        let (json, applied) = migrate(&json!({
+            "version": 1,
+            "canisters": {
+                "app": {
+                    "main": "main.mo",
+                    "initialization_values": { "compute_allocation": 10 },
+                    "networks": {
+                        "ic": { "initialization_values": { "memory_allocation": 4096 } }
+                    }
+                }
+            },
+            "defaults": { "build": { "output": "canisters/", "packtool": "" } },
+            "networks": {
+                "local": { "bind": "127.0.0.1:8000" },
+                "ic": { "providers": [ "https://ic0.app" ] },
+                "test": { "bind": "127.0.0.1:8001", "type": "persistent" }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(applied.len(), 3);
+        assert_eq!(
+            json,
+            json!({
+                "version": 2,
+                "canisters": {
+                    "app": {
+                        "main": "main.mo",
+                        "initialization_values": { "compute_allocation": "10" },
+                        "networks": {
+                            "ic": { "initialization_values": { "memory_allocation": "4096" } }
+                        }
+                    }
+                },
+                "defaults": { "build": { "packtool": "" } },
+                "networks": {
+                    "local": { "bind": "127.0.0.1:8000", "type": "ephemeral" },
+                    "ic": { "providers": [ "https://ic0.app" ], "type": "persistent" },
+                    "test": { "bind": "127.0.0.1:8001", "type": "persistent" }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn migrate_current_version_is_unchanged() {
+        let json = json!({ "version": CURRENT_CONFIG_VERSION, "canisters": {} });
+        let (migrated, applied) = migrate(&json).unwrap();
+        assert_eq!(migrated, json);
+        assert!(applied.is_empty());
+
+        assert!(migrate(&json!({ "version": CURRENT_CONFIG_VERSION + 1 })).is_err());
+        assert!(migrate(&json!({ "version": "1" })).is_err());
+    }
+}
@@ -3,6 +3,9 @@ use semver::Version;
 
 pub mod cache;
 pub mod dfinity;
+pub mod interpolation;
+pub mod migration;
+pub mod schema;
 
 lazy_static! {
     // This expect cannot happen, we make sure that CARGO_PKG_VERSION is correct.
@@ -0,0 +1,1008 @@
+//! A description of the layout of dfx.json.
+//!
+//! The same description is used to validate configuration files (reporting the JSON path,
+//! line and column of each problem) and to emit a JSON Schema document for editors.
+use crate::config::dfinity::BINDINGS;
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// The expected shape of a value in dfx.json.
+pub enum Schema {
+    /// Any value is accepted.
+    Any,
+    String,
+    Integer,
+    Boolean,
+    /// A string taken from a fixed set of values.
+    Enum(Vec<&'static str>),
+    Array(Box<Schema>),
+    /// An object with arbitrary keys, all of the same shape.
+    Map(Box<Schema>),
+    /// An object with a closed set of known keys.
+    Object(Vec<Property>),
+    /// A value that must match one of multiple shapes.
+    OneOf(Vec<Schema>),
+    /// An object whose shape depends on the string value of one of its keys.
+    Tagged {
+        tag: &'static str,
+        default: &'static str,
+        variants: Vec<(&'static str, Schema)>,
+        /// Accepts other values of the tag, with any shape, e.g. the canister types built
+        /// by plugins, which are only looked for when the canisters are built.
+        open: bool,
+    },
+}
+
+pub struct Property {
+    name: &'static str,
+    description: &'static str,
+    required: bool,
+    schema: Schema,
+}
+
+impl Property {
+    fn optional(name: &'static str, description: &'static str, schema: Schema) -> Self {
+        Property {
+            name,
+            description,
+            required: false,
+            schema,
+        }
+    }
+
+    fn required(name: &'static str, description: &'static str, schema: Schema) -> Self {
+        Property {
+            name,
+            description,
+            required: true,
+            schema,
+        }
+    }
+}
+
+fn array_of(schema: Schema) -> Schema {
+    Schema::Array(Box::new(schema))
+}
+
+fn map_of(schema: Schema) -> Schema {
+    Schema::Map(Box::new(schema))
+}
+
+fn network_type() -> Schema {
+    Schema::Enum(vec!["ephemeral", "persistent"])
+}
+
+/// The canister types known to dfx.
+const CANISTER_TYPES: &[&str] = &["motoko", "assets", "custom", "rust"];
+
+/// The properties shared by every canister type.
+fn canister_common_properties(canister_types: Vec<&'static str>) -> Vec<Property> {
+    vec![
+        Property::optional(
+            "type",
+            "The type of the canister. Defaults to \"motoko\".",
+            Schema::Enum(canister_types),
+        ),
+        Property::optional(
+            "dependencies",
+            "The names of the canisters this canister depends on.",
+            array_of(Schema::String),
+        ),
+        Property::optional(
+            "inputs",
+            "Files, directories and glob patterns (e.g. \"src/**/*.py\"), besides those known to the builder, whose changes require the canister to be rebuilt.",
+            array_of(Schema::String),
+        ),
+        Property::optional(
+            "declarations",
+            "Settings of the client bindings generated by `dfx generate`.",
+            Schema::Object(vec![
+                Property::optional(
+                    "output",
+                    "The directory of the bindings. Defaults to \"src/declarations/<canister name>\".",
+                    Schema::String,
+                ),
+                Property::optional(
+                    "bindings",
+                    "The languages to generate bindings for. Defaults to [\"js\", \"ts\", \"did\"].",
+                    array_of(Schema::Enum(BINDINGS.to_vec())),
+                ),
+            ]),
+        ),
+        Property::optional(
+            "initialization_values",
+            "Settings used when the canister is created.",
+            Schema::Object(vec![
+                Property::optional(
+                    "compute_allocation",
+                    "The compute allocation, as a percentage.",
+                    Schema::String,
+                ),
+                Property::optional(
+                    "memory_allocation",
+                    "The memory allocation, e.g. \"8GB\".",
+                    Schema::String,
+                ),
+                Property::optional(
+                    "freezing_threshold",
+                    "The freezing threshold, in seconds.",
+                    Schema::String,
+                ),
+            ]),
+        ),
+    ]
+}
+
+/// The properties specific to a canister type.
+fn canister_type_properties(canister_type: &str) -> Vec<Property> {
+    match canister_type {
+        "motoko" => vec![
+            Property::required(
+                "main",
+                "The path to the main Motoko source file.",
+                Schema::String,
+            ),
+            Property::optional(
+                "frontend",
+                "Frontend settings for this canister.",
+                Schema::Any,
+            ),
+        ],
+        "assets" => vec![
+            Property::optional(
+                "source",
+                "The directories containing the assets to upload.",
+                array_of(Schema::String),
+            ),
+            Property::optional(
+                "compression",
+                "Rules choosing the content encodings stored for the assets. The first rule matching an asset applies.",
+                array_of(Schema::Object(vec![
+                    Property::required(
+                        "match",
+                        "A glob pattern matching the keys of the assets, e.g. \"/fonts/**\". Patterns without '/' match file names.",
+                        Schema::String,
+                    ),
+                    Property::required(
+                        "encodings",
+                        "The content encodings stored besides identity.",
+                        array_of(Schema::Enum(vec!["gzip", "br"])),
+                    ),
+                    Property::optional(
+                        "level",
+                        "The compression level: 0 to 9 for gzip, 0 to 11 for br.",
+                        Schema::Integer,
+                    ),
+                ])),
+            ),
+        ],
+        "custom" => vec![
+            Property::required(
+                "wasm",
+                "The path to the WASM module produced by the build.",
+                Schema::String,
+            ),
+            Property::required(
+                "candid",
+                "The path to the Candid interface of the canister.",
+                Schema::String,
+            ),
+            Property::optional(
+                "build",
+                "The command(s) used to build the canister.",
+                Schema::OneOf(vec![Schema::String, array_of(Schema::String)]),
+            ),
+            Property::optional(
+                "workdir",
+                "The directory the build commands run in, relative to the project.",
+                Schema::String,
+            ),
+            Property::optional(
+                "env",
+                "Environment variables to set for the build commands.",
+                map_of(Schema::String),
+            ),
+        ],
+        "rust" => vec![
+            Property::required(
+                "package",
+                "The name of the cargo package to build.",
+                Schema::String,
+            ),
+            Property::required(
+                "candid",
+                "The path to the Candid interface of the canister.",
+                Schema::String,
+            ),
+        ],
+        _ => vec![],
+    }
+}
+
+/// The schema of a per-network override. Since an override can change the type of the
+/// canister, it accepts the properties of every canister type, none of them required.
+fn canister_override_schema() -> Schema {
+    let mut properties = canister_common_properties(CANISTER_TYPES.to_vec());
+    for canister_type in CANISTER_TYPES {
+        for mut property in canister_type_properties(canister_type) {
+            property.required = false;
+            if properties.iter().all(|p| p.name != property.name) {
+                properties.push(property);
+            }
+        }
+    }
+    Schema::Object(properties)
+}
+
+fn canister_schema(canister_type: &'static str) -> Schema {
+    let mut properties = canister_common_properties(vec![canister_type]);
+    properties.extend(canister_type_properties(canister_type));
+    properties.push(Property::optional(
+        "networks",
+        "Overrides of this canister's configuration, keyed by network name.",
+        map_of(canister_override_schema()),
+    ));
+    Schema::Object(properties)
+}
+
+fn canisters_schema() -> Schema {
+    Schema::Tagged {
+        tag: "type",
+        default: "motoko",
+        variants: CANISTER_TYPES
+            .iter()
+            .map(|canister_type| (*canister_type, canister_schema(*canister_type)))
+            .collect(),
+        open: true,
+    }
+}
+
+fn defaults_schema() -> Schema {
+    Schema::Object(vec![
+        Property::optional(
+            "bootstrap",
+            "Defaults for `dfx bootstrap`.",
+            Schema::Object(vec![
+                Property::optional("ip", "The IP address to bind to.", Schema::String),
+                Property::optional("port", "The port to bind to.", Schema::Integer),
+                Property::optional(
+                    "timeout",
+                    "The request timeout, in seconds.",
+                    Schema::Integer,
+                ),
+            ]),
+        ),
+        Property::optional(
+            "build",
+            "Defaults for `dfx build`.",
+            Schema::Object(vec![
+                Property::optional(
+                    "packtool",
+                    "The command used to resolve Motoko packages.",
+                    Schema::String,
+                ),
+                Property::optional(
+                    "output",
+                    "Deprecated. This value is ignored.",
+                    Schema::String,
+                ),
+            ]),
+        ),
+        Property::optional(
+            "replica",
+            "Defaults for `dfx replica`.",
+            Schema::Object(vec![
+                Property::optional(
+                    "message_gas_limit",
+                    "The maximum gas a message can use.",
+                    Schema::Integer,
+                ),
+                Property::optional("port", "The port to bind to.", Schema::Integer),
+                Property::optional(
+                    "round_gas_limit",
+                    "The maximum gas a round can use.",
+                    Schema::Integer,
+                ),
+            ]),
+        ),
+    ])
+}
+
+fn networks_schema() -> Schema {
+    map_of(Schema::OneOf(vec![
+        Schema::Object(vec![
+            Property::required(
+                "providers",
+                "The URLs of the network providers.",
+                array_of(Schema::String),
+            ),
+            Property::optional(
+                "type",
+                "Where canister IDs for this network are stored.",
+                network_type(),
+            ),
+        ]),
+        Schema::Object(vec![
+            Property::required(
+                "bind",
+                "The address the local replica binds to.",
+                Schema::String,
+            ),
+            Property::optional(
+                "type",
+                "Where canister IDs for this network are stored.",
+                network_type(),
+            ),
+        ]),
+    ]))
+}
+
+fn profile_schema() -> Schema {
+    Schema::Object(vec![
+        Property::optional(
+            "codegen",
+            "The code generation of the profile.",
+            Schema::Enum(vec!["Debug", "Release"]),
+        ),
+        Property::optional(
+            "args",
+            "Extra arguments passed to the compiler, keyed by canister type.",
+            map_of(array_of(Schema::String)),
+        ),
+        Property::optional(
+            "env",
+            "Environment variables set for the build commands of custom canisters.",
+            map_of(Schema::String),
+        ),
+        Property::optional(
+            "output",
+            "A directory where the Wasm module and Candid interface of the canisters are copied.",
+            Schema::String,
+        ),
+        Property::optional(
+            "strip",
+            "Remove the names and custom sections of the Wasm modules. Defaults to true for Release.",
+            Schema::Boolean,
+        ),
+        Property::optional(
+            "keep_sections",
+            "The custom sections kept in the Wasm modules when stripping them.",
+            array_of(Schema::String),
+        ),
+        Property::optional(
+            "max_wasm_size",
+            "The maximum size of a Wasm module, e.g. \"2MB\". Larger modules fail the build.",
+            Schema::String,
+        ),
+    ])
+}
+
+/// Returns the schema of a dfx.json file.
+pub fn dfx_json_schema() -> Schema {
+    Schema::Object(vec![
+        Property::optional(
+            "profile",
+            "The default build profile: Debug, Release or the name of a profile in `profiles`.",
+            Schema::String,
+        ),
+        Property::optional(
+            "profiles",
+            "Named build profiles, selected with `--profile`.",
+            map_of(profile_schema()),
+        ),
+        Property::optional(
+            "version",
+            "The version of the configuration file layout. `dfx config migrate` updates it to the latest version.",
+            Schema::Integer,
+        ),
+        Property::optional("dfx", "The version of dfx to use.", Schema::String),
+        Property::optional(
+            "canisters",
+            "The canisters of the project.",
+            map_of(canisters_schema()),
+        ),
+        Property::optional(
+            "defaults",
+            "Default settings for dfx commands.",
+            defaults_schema(),
+        ),
+        Property::optional(
+            "networks",
+            "The networks the project can be deployed to.",
+            networks_schema(),
+        ),
+        Property::optional(
+            "workspace",
+            "The projects included in this workspace.",
+            Schema::Object(vec![Property::required(
+                "members",
+                "The directories of the projects, relative to this file. Their canisters are named `<member>.<canister>`.",
+                array_of(Schema::String),
+            )]),
+        ),
+    ])
+}
+
+impl Schema {
+    /// Converts this schema into a JSON Schema (draft-07) document.
+    pub fn to_json_schema(&self) -> Value {
+        match self {
+            Schema::Any => json!({}),
+            Schema::String => json!({ "type": "string" }),
+            Schema::Integer => json!({ "type": "integer" }),
+            Schema::Boolean => json!({ "type": "boolean" }),
+            Schema::Enum(values) => json!({ "enum": values }),
+            Schema::Array(items) => json!({ "type": "array", "items": items.to_json_schema() }),
+            Schema::Map(values) => {
+                json!({ "type": "object", "additionalProperties": values.to_json_schema() })
+            }
+            Schema::Object(properties) => {
+                let mut props = Map::new();
+                for property in properties {
+                    let mut schema = property.schema.to_json_schema();
+                    if let Some(object) = schema.as_object_mut() {
+                        object.insert(
+                            "description".to_string(),
+                            Value::String(property.description.to_string()),
+                        );
+                    }
+                    props.insert(property.name.to_string(), schema);
+                }
+                let required: Vec<&str> = properties
+                    .iter()
+                    .filter(|p| p.required)
+                    .map(|p| p.name)
+                    .collect();
+                json!({
+                    "type": "object",
+                    "properties": props,
+                    "required": required,
+                    "additionalProperties": false,
+                })
+            }
+            Schema::OneOf(schemas) => json!({
+                "oneOf": schemas.iter().map(Schema::to_json_schema).collect::<Vec<Value>>()
+            }),
+            Schema::Tagged {
+                tag,
+                default,
+                variants,
+                ..
+            } => {
+                let variants: Vec<Value> = variants
+                    .iter()
+                    .map(|(name, schema)| {
+                        let mut schema = schema.to_json_schema();
+                        if name != default {
+                            if let Some(required) =
+                                schema.get_mut("required").and_then(Value::as_array_mut)
+                            {
+                                required.push(Value::String(tag.to_string()));
+                            }
+                        }
+                        schema
+                    })
+                    .collect();
+                json!({ "type": "object", "oneOf": variants })
+            }
+        }
+    }
+}
+
+/// A problem found while validating a configuration file.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConfigDiagnostic {
+    /// The path to the offending value, e.g. `canisters.hello.main`.
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    /// The nearest valid key or value, if any.
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        f.write_str(&self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " Did you mean '{}'?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates the content of a dfx.json file. Returns an error if the content is not JSON.
+pub fn validate_str(content: &str) -> Result<Vec<ConfigDiagnostic>, serde_json::Error> {
+    let json = serde_json::from_str::<Value>(content)?;
+    Ok(validate(&dfx_json_schema(), &json, Some(content)))
+}
+
+/// Validates a JSON value against a schema. If the source text is passed, diagnostics will
+/// carry the line and column of the offending value; otherwise they will be zero.
+pub fn validate(schema: &Schema, json: &Value, content: Option<&str>) -> Vec<ConfigDiagnostic> {
+    let mut validator = Validator {
+        positions: content.map(locate_positions).unwrap_or_default(),
+        diagnostics: vec![],
+    };
+    validator.check(schema, json, &mut vec![]);
+    validator.diagnostics
+}
+
+struct Validator {
+    positions: BTreeMap<String, (usize, usize)>,
+    diagnostics: Vec<ConfigDiagnostic>,
+}
+
+impl Validator {
+    fn report(&mut self, path: &[String], message: String, suggestion: Option<String>) {
+        // Find the position of the closest ancestor we know about.
+        let (line, column) = (0..=path.len())
+            .rev()
+            .find_map(|len| self.positions.get(&to_pointer(&path[..len])))
+            .cloned()
+            .unwrap_or((0, 0));
+        self.diagnostics.push(ConfigDiagnostic {
+            path: path.join("."),
+            line,
+            column,
+            message,
+            suggestion,
+        });
+    }
+
+    fn check(&mut self, schema: &Schema, value: &Value, path: &mut Vec<String>) {
+        match schema {
+            Schema::Any => {}
+            Schema::String => {
+                if !value.is_string() {
+                    self.report(path, wrong_type("a string", value), None);
+                }
+            }
+            Schema::Integer => {
+                if !value.is_u64() && !value.is_i64() {
+                    self.report(path, wrong_type("an integer", value), None);
+                }
+            }
+            Schema::Boolean => {
+                if !value.is_boolean() {
+                    self.report(path, wrong_type("a boolean", value), None);
+                }
+            }
+            Schema::Enum(values) => match value.as_str() {
+                Some(s) if values.iter().any(|v| *v == s) => {}
+                Some(s) => {
+                    let suggestion = nearest(s, values.iter().copied());
+                    self.report(
+                        path,
+                        format!(
+                            "Invalid value '{}', expected one of: {}.",
+                            s,
+                            values.join(", ")
+                        ),
+                        suggestion,
+                    );
+                }
+                None => self.report(path, wrong_type("a string", value), None),
+            },
+            Schema::Array(items) => match value.as_array() {
+                Some(array) => {
+                    for (i, item) in array.iter().enumerate() {
+                        path.push(i.to_string());
+                        self.check(items, item, path);
+                        path.pop();
+                    }
+                }
+                None => self.report(path, wrong_type("an array", value), None),
+            },
+            Schema::Map(values) => match value.as_object() {
+                Some(object) => {
+                    for (key, item) in object {
+                        path.push(key.clone());
+                        self.check(values, item, path);
+                        path.pop();
+                    }
+                }
+                None => self.report(path, wrong_type("an object", value), None),
+            },
+            Schema::Object(properties) => match value.as_object() {
+                Some(object) => self.check_object(properties, object, path),
+                None => self.report(path, wrong_type("an object", value), None),
+            },
+            Schema::OneOf(schemas) => {
+                // Report the problems of the alternative that came the closest to matching.
+                let mut best: Option<Vec<ConfigDiagnostic>> = None;
+                for schema in schemas {
+                    let mut alternative = Validator {
+                        positions: std::mem::take(&mut self.positions),
+                        diagnostics: vec![],
+                    };
+                    alternative.check(schema, value, path);
+                    self.positions = alternative.positions;
+                    if best
+                        .as_ref()
+                        .map_or(true, |b| alternative.diagnostics.len() < b.len())
+                    {
+                        best = Some(alternative.diagnostics);
+                    }
+                }
+                self.diagnostics.extend(best.unwrap_or_default());
+            }
+            Schema::Tagged {
+                tag,
+                default,
+                variants,
+                open,
+            } => match value.as_object() {
+                Some(object) => {
+                    let tag_value = object.get(*tag).and_then(Value::as_str).unwrap_or(*default);
+                    match variants.iter().find(|(name, _)| *name == tag_value) {
+                        Some((_, schema)) => self.check(schema, value, path),
+                        None if *open => {}
+                        None => {
+                            let suggestion =
+                                nearest(tag_value, variants.iter().map(|(name, _)| *name));
+                            path.push(tag.to_string());
+                            self.report(
+                                path,
+                                format!("Unknown {} '{}'.", tag, tag_value),
+                                suggestion,
+                            );
+                            path.pop();
+                        }
+                    }
+                }
+                None => self.report(path, wrong_type("an object", value), None),
+            },
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        properties: &[Property],
+        object: &Map<String, Value>,
+        path: &mut Vec<String>,
+    ) {
+        for (key, item) in object {
+            path.push(key.clone());
+            match properties.iter().find(|p| p.name == key.as_str()) {
+                // Optional values can be explicitly set to null.
+                Some(property) if item.is_null() && !property.required => {}
+                Some(property) => self.check(&property.schema, item, path),
+                None => {
+                    let suggestion = nearest(key, properties.iter().map(|p| p.name));
+                    self.report(path, format!("Unknown field '{}'.", key), suggestion);
+                }
+            }
+            path.pop();
+        }
+
+        for property in properties.iter().filter(|p| p.required) {
+            if !object.contains_key(property.name) {
+                self.report(
+                    path,
+                    format!("Missing required field '{}'.", property.name),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+fn wrong_type(expected: &str, value: &Value) -> String {
+    let found = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    };
+    format!("Expected {}, found {}.", expected, found)
+}
+
+/// Returns the candidate closest to `name`, if it is close enough to be a likely typo.
+fn nearest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let max_distance = std::cmp::max(2, name.chars().count() / 3);
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn to_pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Maps the JSON pointer of every value in a JSON document to its (1-based) line and column.
+/// Object members are located at their key. This is a best effort; positions after a syntax
+/// error are not recorded.
+fn locate_positions(content: &str) -> BTreeMap<String, (usize, usize)> {
+    let mut locator = Locator {
+        chars: content.chars().peekable(),
+        line: 1,
+        column: 1,
+        positions: BTreeMap::new(),
+    };
+    let _ = locator.value(&[]);
+    locator.positions
+}
+
+struct Locator<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+    positions: BTreeMap<String, (usize, usize)>,
+}
+
+impl Locator<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn value(&mut self, path: &[String]) -> Option<()> {
+        self.skip_whitespace();
+        self.positions
+            .entry(to_pointer(path))
+            .or_insert((self.line, self.column));
+
+        match self.peek()? {
+            '{' => {
+                self.bump();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        '}' => {
+                            self.bump();
+                            return Some(());
+                        }
+                        ',' => {
+                            self.bump();
+                        }
+                        '"' => {
+                            let position = (self.line, self.column);
+                            let key = self.string()?;
+                            let mut child = path.to_vec();
+                            child.push(key);
+                            self.positions.insert(to_pointer(&child), position);
+                            self.skip_whitespace();
+                            if self.bump()? != ':' {
+                                return None;
+                            }
+                            self.value(&child)?;
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            '[' => {
+                self.bump();
+                let mut index = 0;
+                loop {
+                    self.skip_whitespace();
+                    match self.peek()? {
+                        ']' => {
+                            self.bump();
+                            return Some(());
+                        }
+                        ',' => {
+                            self.bump();
+                        }
+                        _ => {
+                            let mut child = path.to_vec();
+                            child.push(index.to_string());
+                            self.value(&child)?;
+                            index += 1;
+                        }
+                    }
+                }
+            }
+            '"' => self.string().map(|_| ()),
+            _ => {
+                while self
+                    .peek()
+                    .map_or(false, |c| !c.is_whitespace() && !",]}".contains(c))
+                {
+                    self.bump();
+                }
+                Some(())
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let mut result = String::new();
+        self.bump()?;
+        loop {
+            match self.bump()? {
+                '"' => return Some(result),
+                '\\' => match self.bump()? {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    'r' => result.push('\r'),
+                    'b' => result.push('\u{8}'),
+                    'f' => result.push('\u{c}'),
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| self.bump()).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(std::char::from_u32)
+                            .unwrap_or(std::char::REPLACEMENT_CHARACTER);
+                        result.push(c);
+                    }
+                    c => result.push(c),
+                },
+                c => result.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_config_has_no_diagnostics() {
+        let diagnostics = validate_str(
+            r#"{
+              "version": 1,
+              "canisters": {
+                "hello": {
+                  "type": "motoko",
+                  "main": "src/hello/main.mo"
+                },
+                "hello_assets": {
+                  "type": "assets",
+                  "source": [ "src/hello_assets/assets" ],
+                  "dependencies": [ "hello" ]
+                },
+                "custom": {
+                  "type": "custom",
+                  "wasm": "main.wasm",
+                  "candid": "main.did",
+                  "build": [ "make" ],
+                  "networks": {
+                    "local": { "type": "motoko", "main": "mock.mo" }
+                  }
+                }
+              },
+              "defaults": { "build": { "packtool": "" } },
+              "networks": {
+                "local": { "bind": "127.0.0.1:8000", "type": "ephemeral" },
+                "staging": { "providers": [ "https://1.2.3.4:5000" ] }
+              }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn unknown_key_suggests_nearest() {
+        let diagnostics = validate_str(
+            r#"{
+  "canisters": {
+    "hello": {
+      "main": "main.mo",
+      "dependancies": []
+    }
+  }
+}"#,
+        )
+        .unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.path, "canisters.hello.dependancies");
+        assert_eq!((diagnostic.line, diagnostic.column), (5, 7));
+        assert_eq!(diagnostic.suggestion.as_deref(), Some("dependencies"));
+    }
+
+    #[test]
+    fn wrong_type_is_reported() {
+        let diagnostics = validate_str(
+            r#"{
+  "canisters": {
+    "hello": {
+      "main": "main.mo",
+      "initialization_values": { "compute_allocation": 100 }
+    }
+  }
+}"#,
+        )
+        .unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].path,
+            "canisters.hello.initialization_values.compute_allocation"
+        );
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (5, 34));
+        assert_eq!(diagnostics[0].message, "Expected a string, found a number.");
+    }
+
+    #[test]
+    fn canister_keys_depend_on_type() {
+        let diagnostics = validate_str(
+            r#"{
+              "canisters": {
+                "custom": { "type": "custom", "wasm": "a.wasm", "candid": "a.did", "main": "x" },
+                "other": { "type": "python", "entry": "main.py" }
+              }
+            }"#,
+        )
+        .unwrap();
+
+        let paths: Vec<&str> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["canisters.custom.main"]);
+    }
+
+    #[test]
+    fn network_overrides_are_checked() {
+        let diagnostics = validate_str(
+            r#"{
+              "canisters": {
+                "hello": {
+                  "main": "main.mo",
+                  "networks": { "ic": { "mian": "other.mo" } }
+                }
+              }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "canisters.hello.networks.ic.mian");
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn network_reports_closest_alternative() {
+        let diagnostics = validate_str(r#"{ "networks": { "local": { "bind": 8000 } } }"#).unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "networks.local.bind");
+    }
+}
@@ -1,12 +1,14 @@
 use crate::config::cache::Cache;
 use crate::config::dfx_version;
 use crate::lib::builders::{
-    BuildConfig, BuildOutput, CanisterBuilder, IdlBuildOutput, WasmBuildOutput,
+    canister_identifier, declared_inputs, prefix_lines, BuildConfig, BuildOutput, CanisterBuilder,
+    IdlBuildOutput, WasmBuildOutput,
 };
 use crate::lib::canister_info::assets::AssetsCanisterInfo;
 use crate::lib::canister_info::CanisterInfo;
 use crate::lib::environment::Environment;
 use crate::lib::error::{BuildError, DfxError, DfxResult};
+use crate::lib::installers::assets::ASSETS_CONFIG_FILE_NAME;
 use crate::lib::models::canister::CanisterPool;
 use crate::util;
 
@@ -14,7 +16,7 @@ use anyhow::{anyhow, bail};
 use ic_types::principal::Principal as CanisterId;
 use serde::Deserialize;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use walkdir::WalkDir;
 
@@ -71,6 +73,27 @@ impl CanisterBuilder for AssetsBuilder {
         Ok(AssetsBuilderExtra::try_from(info, pool)?.dependencies)
     }
 
+    /// The asset sources and the declared inputs. If the project has a frontend
+    /// (a package.json), its inputs must be declared, or the canister is always built.
+    fn get_inputs(
+        &self,
+        _pool: &CanisterPool,
+        info: &CanisterInfo,
+    ) -> DfxResult<Option<Vec<PathBuf>>> {
+        let package_json = info.get_workspace_root().join("package.json");
+        let mut inputs = declared_inputs(info)?;
+        if package_json.exists() {
+            if inputs.is_empty() {
+                return Ok(None);
+            }
+            inputs.push(package_json);
+        }
+
+        let assets_canister_info = info.as_info::<AssetsCanisterInfo>()?;
+        inputs.extend(assets_canister_info.get_source_paths().iter().cloned());
+        Ok(Some(inputs))
+    }
+
     fn build(
         &self,
         _pool: &CanisterPool,
@@ -128,6 +151,7 @@ impl CanisterBuilder for AssetsBuilder {
             &config.network_name,
             dependencies,
             pool,
+            &config.get_output_prefix(info),
         )?;
 
         let assets_canister_info = info.as_info::<AssetsCanisterInfo>()?;
@@ -153,9 +177,10 @@ fn delete_output_directory(
     let output_assets_path = assets_canister_info.get_output_assets_path();
     if output_assets_path.exists() {
         let output_assets_path = output_assets_path.canonicalize()?;
-        if !output_assets_path.starts_with(info.get_workspace_root()) {
+        // The output of a canister of a workspace member is outside of the member.
+        if !output_assets_path.starts_with(info.get_build_root()) {
             bail!(
-                "Directory at '{}' is outside the workspace root.",
+                "Directory at '{}' is outside the build root.",
                 output_assets_path.display()
             );
         }
@@ -182,7 +207,10 @@ fn copy_assets(logger: &slog::Logger, assets_canister_info: &AssetsCanisterInfo)
 
         let input_assets_path = source_path.as_path();
         let walker = WalkDir::new(input_assets_path).into_iter();
-        for entry in walker.filter_entry(|e| !is_hidden(e)) {
+        // The rules of the assets are hidden files.
+        for entry in
+            walker.filter_entry(|e| !is_hidden(e) || e.file_name() == ASSETS_CONFIG_FILE_NAME)
+        {
             let entry = entry?;
             let source = entry.path();
             let relative = source
@@ -214,13 +242,14 @@ fn build_frontend(
     network_name: &str,
     dependencies: Vec<CanisterId>,
     pool: &CanisterPool,
+    output_prefix: &str,
 ) -> DfxResult {
     let build_frontend = project_root.join("package.json").exists();
     // If there is not a package.json, we don't have a frontend and can quit early.
 
     if build_frontend {
         // Frontend build.
-        slog::info!(logger, "Building frontend...");
+        slog::info!(logger, "{}Building frontend...", output_prefix);
         let mut cmd = std::process::Command::new("npm");
         cmd.arg("run")
             .arg("build")
@@ -235,14 +264,17 @@ fn build_frontend(
                 };
 
                 cmd.env(
-                    format!("CANISTER_CANDID_PATH_{}", canister.get_name()),
+                    format!(
+                        "CANISTER_CANDID_PATH_{}",
+                        canister_identifier(canister.get_name())
+                    ),
                     candid_path,
                 );
             }
         }
         for canister in pool.get_canister_list() {
             cmd.env(
-                format!("CANISTER_ID_{}", canister.get_name()),
+                format!("CANISTER_ID_{}", canister_identifier(canister.get_name())),
                 canister.canister_id().to_text(),
             );
         }
@@ -262,7 +294,7 @@ fn build_frontend(
             )));
         } else if !output.stderr.is_empty() {
             // Cannot use eprintln, because it would interfere with the progress bar.
-            slog::warn!(logger, "{}", String::from_utf8_lossy(&output.stderr));
+            slog::warn!(logger, "{}", prefix_lines(output_prefix, &output.stderr));
         }
     }
     Ok(())
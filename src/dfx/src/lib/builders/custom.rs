@@ -1,7 +1,9 @@
 use crate::lib::builders::{
-    BuildConfig, BuildOutput, CanisterBuilder, IdlBuildOutput, WasmBuildOutput,
+    declared_dependencies, declared_inputs, run_build_command, set_canister_env, BuildConfig,
+    BuildOutput, CanisterBuilder, IdlBuildOutput, WasmBuildOutput,
 };
 use crate::lib::canister_info::CanisterInfo;
+use crate::lib::diagnostics::emit_diagnostics;
 use crate::lib::environment::Environment;
 use crate::lib::error::{BuildError, DfxError, DfxResult};
 use crate::lib::models::canister::CanisterPool;
@@ -12,8 +14,8 @@ use ic_types::principal::Principal as CanisterId;
 use serde::Deserialize;
 use slog::info;
 use slog::Logger;
-use std::path::{Path, PathBuf};
-use std::process::Stdio;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 /// Set of extras that can be specified in the dfx.json.
 struct CustomBuilderExtra {
@@ -26,26 +28,15 @@ struct CustomBuilderExtra {
     /// A command to run to build this canister. This is optional if the canister
     /// only needs to exist.
     build: Vec<String>,
+    /// The directory the build commands run in. Defaults to the workspace root.
+    workdir: PathBuf,
+    /// Environment variables to set for the build commands.
+    env: BTreeMap<String, String>,
 }
 
 impl CustomBuilderExtra {
     fn try_from(info: &CanisterInfo, pool: &CanisterPool) -> DfxResult<Self> {
-        let deps = match info.get_extra_value("dependencies") {
-            None => vec![],
-            Some(v) => Vec::<String>::deserialize(v)
-                .map_err(|_| anyhow!("Field 'dependencies' is of the wrong type."))?,
-        };
-        let dependencies = deps
-            .iter()
-            .map(|name| {
-                pool.get_first_canister_with_name(name)
-                    .map(|c| c.canister_id())
-                    .map_or_else(
-                        || Err(anyhow!("A canister with the name '{}' was not found in the current project.", name.clone())),
-                        DfxResult::Ok,
-                    )
-            })
-            .collect::<DfxResult<Vec<CanisterId>>>()?;
+        let dependencies = declared_dependencies(pool, info)?;
 
         let wasm = info
             .get_output_wasm_path()
@@ -62,22 +53,33 @@ impl CustomBuilderExtra {
         } else {
             vec![]
         };
+        let workdir = match info.get_extra_value("workdir") {
+            None => info.get_workspace_root().to_path_buf(),
+            Some(v) => info.get_workspace_root().join(
+                PathBuf::deserialize(v)
+                    .map_err(|_| anyhow!("Field 'workdir' is of the wrong type."))?,
+            ),
+        };
+        let env = match info.get_extra_value("env") {
+            None => BTreeMap::new(),
+            Some(v) => BTreeMap::<String, String>::deserialize(v)
+                .map_err(|_| anyhow!("Field 'env' is of the wrong type."))?,
+        };
 
         Ok(CustomBuilderExtra {
             dependencies,
             wasm,
             candid,
             build,
+            workdir,
+            env,
         })
     }
 }
 
 /// A Builder for a WASM type canister, which has an optional build step.
-/// This will set environment variables for the external tool;
-///   `CANISTER_ID`     => Its own canister ID (in textual format).
-///   `CANDID_PATH`     => Its own candid path.
-///   `CANISTER_ID_{}`  => The canister ID of all dependencies. `{}` is replaced by the name.
-///   `CANDID_{}`       => The candid path of all dependencies. `{}` is replaced by the name.
+/// The build commands run in the `workdir` of the canister, with its `env` and the
+/// variables set by [`set_canister_env`].
 pub struct CustomBuilder {
     logger: Logger,
 }
@@ -103,22 +105,41 @@ impl CanisterBuilder for CustomBuilder {
         Ok(CustomBuilderExtra::try_from(info, pool)?.dependencies)
     }
 
-    fn build(
+    /// The declared inputs, and the Wasm and Candid files unless build commands write them.
+    /// A canister with build commands but no declared inputs is always built, as the inputs
+    /// of the commands are unknown.
+    fn get_inputs(
         &self,
         pool: &CanisterPool,
         info: &CanisterInfo,
-        _config: &BuildConfig,
-    ) -> DfxResult<BuildOutput> {
+    ) -> DfxResult<Option<Vec<PathBuf>>> {
         let CustomBuilderExtra {
-            candid,
             wasm,
+            candid,
             build,
-            dependencies,
+            ..
         } = CustomBuilderExtra::try_from(info, pool)?;
+        let mut inputs = declared_inputs(info)?;
+        if build.is_empty() {
+            inputs.push(wasm);
+            inputs.push(candid);
+        } else if inputs.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(inputs))
+    }
+
+    fn build(
+        &self,
+        pool: &CanisterPool,
+        info: &CanisterInfo,
+        config: &BuildConfig,
+    ) -> DfxResult<BuildOutput> {
+        let extra = CustomBuilderExtra::try_from(info, pool)?;
 
         let canister_id = info.get_canister_id().unwrap();
 
-        for command in build {
+        for command in &extra.build {
             info!(
                 self.logger,
                 r#"{} '{}'"#,
@@ -131,59 +152,59 @@ impl CanisterBuilder for CustomBuilder {
                 .context(format!("Cannot parse command '{}'.", command))?;
             // No commands, noop.
             if !args.is_empty() {
-                run_command(args, &canister_id, &candid, dependencies.clone(), pool)?;
+                let stderr = run_command(
+                    args,
+                    &extra,
+                    &canister_id,
+                    pool,
+                    config,
+                    &self.logger,
+                    &config.get_output_prefix(info),
+                )?;
+                emit_diagnostics(config.message_format, info.get_name(), &stderr);
             }
         }
 
         Ok(BuildOutput {
             canister_id,
-            wasm: WasmBuildOutput::File(wasm),
-            idl: IdlBuildOutput::File(candid),
+            wasm: WasmBuildOutput::File(extra.wasm),
+            idl: IdlBuildOutput::File(extra.candid),
         })
     }
 }
 
+/// Run a build command of a custom canister. Returns what it wrote to stderr.
 fn run_command(
     args: Vec<String>,
+    extra: &CustomBuilderExtra,
     canister_id: &CanisterId,
-    candid: &Path,
-    dependencies: Vec<CanisterId>,
     pool: &CanisterPool,
-) -> DfxResult<()> {
+    config: &BuildConfig,
+    logger: &Logger,
+    output_prefix: &str,
+) -> DfxResult<String> {
     let (command_name, arguments) = args.split_first().unwrap();
 
     let mut cmd = std::process::Command::new(command_name);
-
-    cmd.args(arguments)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .env("CANISTER_ID", canister_id.to_text())
-        .env("CANISTER_CANDID_PATH", candid.as_os_str());
-
-    for deps in &dependencies {
-        let canister = pool.get_canister(deps).unwrap();
-        cmd.env(
-            format!("CANISTER_ID_{}", canister.get_name()),
-            deps.to_text(),
-        );
-        if let Some(output) = canister.get_build_output() {
-            let candid_path = match &output.idl {
-                IdlBuildOutput::File(p) => p.as_os_str(),
-            };
-
-            cmd.env(
-                format!("CANISTER_CANDID_{}", canister.get_name()),
-                candid_path,
-            );
-        }
-    }
-
-    let output = cmd.output().expect("Could not run custom tool.");
-    if output.status.success() {
-        Ok(())
+    cmd.args(arguments).current_dir(&extra.workdir);
+    set_canister_env(
+        &mut cmd,
+        pool,
+        config,
+        canister_id,
+        &extra.candid,
+        &extra.dependencies,
+    );
+    cmd.envs(&extra.env);
+
+    let (status, stderr) = run_build_command(logger, &mut cmd, output_prefix)
+        .with_context(|| format!("Cannot run '{}'.", command_name))?;
+    if status.success() {
+        Ok(stderr)
     } else {
         Err(DfxError::new(BuildError::CustomToolError(
-            output.status.code(),
+            status.code(),
+            stderr,
         )))
     }
 }
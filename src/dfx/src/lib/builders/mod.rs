@@ -1,17 +1,32 @@
 use crate::config::dfinity::{Config, Profile};
 use crate::lib::canister_info::CanisterInfo;
+use crate::lib::diagnostics::MessageFormat;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
 
+use crate::lib::metadata::git_revision;
 use crate::lib::models::canister::CanisterPool;
 use crate::lib::provider::get_network_context;
+use crate::util::glob::{glob_to_regex, is_glob};
+use anyhow::anyhow;
+use humanize_rs::bytes::Bytes;
 use ic_types::principal::Principal as CanisterId;
-use std::path::PathBuf;
+use serde::Deserialize;
+use slog::{info, warn, Logger};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::Arc;
+use walkdir::WalkDir;
 
 mod assets;
 mod custom;
 mod motoko;
+mod plugin;
+mod rust;
+
+pub use motoko::supports_stable_types;
 
 #[derive(Debug)]
 pub enum WasmBuildOutput {
@@ -32,8 +47,10 @@ pub struct BuildOutput {
     pub idl: IdlBuildOutput,
 }
 
-/// A stateless canister builder. This is meant to not keep any state and be passed everything.
-pub trait CanisterBuilder {
+/// A canister builder. This is meant to be passed everything, and only keeps what it finds
+/// about the canisters during a build (see `clear_cache`).
+/// Canisters are built in parallel, so builders are shared between threads.
+pub trait CanisterBuilder: Send + Sync {
     /// Returns true if this builder supports building the canister.
     fn supports(&self, info: &CanisterInfo) -> bool;
 
@@ -47,6 +64,20 @@ pub trait CanisterBuilder {
         Ok(Vec::new())
     }
 
+    /// Returns the files and directories whose content determines the output of the build,
+    /// or None if they cannot be known, in which case the canister is always built.
+    fn get_inputs(
+        &self,
+        _pool: &CanisterPool,
+        _info: &CanisterInfo,
+    ) -> DfxResult<Option<Vec<PathBuf>>> {
+        Ok(None)
+    }
+
+    /// Forget what was found about the canisters for the previous build, e.g. their imports,
+    /// as it may have changed since.
+    fn clear_cache(&self) {}
+
     fn prebuild(
         &self,
         _pool: &CanisterPool,
@@ -75,44 +106,347 @@ pub trait CanisterBuilder {
     }
 }
 
+/// Prefix every line of the output of a command.
+fn prefix_lines(prefix: &str, output: &[u8]) -> String {
+    String::from_utf8_lossy(output)
+        .lines()
+        .map(|line| format!("{}{}", prefix, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The name of a canister as an identifier, for Motoko imports and environment variables.
+/// The `.` of the names of the canisters of workspace members is replaced by `_`, e.g.
+/// `backend_api` for `backend.api`.
+pub fn canister_identifier(canister_name: &str) -> String {
+    canister_name.replace('.', "_")
+}
+
+/// Set the environment of an external build command:
+///   `DFX_BUILD_PROFILE`    => The name of the build profile, with the variables of the profile.
+///   `DFX_NETWORK`          => The name of the network the canister is built for.
+///   `CANISTER_ID`          => The canister ID of the canister being built.
+///   `CANISTER_CANDID_PATH` => Its own candid path.
+///   `CANISTER_ID_{}`       => The canister ID of all dependencies. `{}` is replaced by the
+///                             `canister_identifier` of their name.
+///   `CANISTER_CANDID_{}`   => The candid path of all dependencies. `{}` is replaced by the
+///                             `canister_identifier` of their name.
+fn set_canister_env(
+    cmd: &mut Command,
+    pool: &CanisterPool,
+    config: &BuildConfig,
+    canister_id: &CanisterId,
+    candid: &Path,
+    dependencies: &[CanisterId],
+) {
+    cmd.envs(&config.env)
+        .env("DFX_BUILD_PROFILE", &config.profile_name)
+        .env("DFX_NETWORK", &config.network_name)
+        .env("CANISTER_ID", canister_id.to_text())
+        .env("CANISTER_CANDID_PATH", candid.as_os_str());
+
+    for deps in dependencies {
+        let canister = pool.get_canister(deps).unwrap();
+        cmd.env(
+            format!("CANISTER_ID_{}", canister_identifier(canister.get_name())),
+            deps.to_text(),
+        );
+        if let Some(output) = canister.get_build_output() {
+            let candid_path = match &output.idl {
+                IdlBuildOutput::File(p) => p.as_os_str(),
+            };
+
+            cmd.env(
+                format!(
+                    "CANISTER_CANDID_{}",
+                    canister_identifier(canister.get_name())
+                ),
+                candid_path,
+            );
+        }
+    }
+}
+
+/// Run an external build command. Its output is logged line by line as it is written,
+/// with the output prefix. Returns the exit status of the command and what it wrote to
+/// stderr.
+fn run_build_command(
+    logger: &Logger,
+    cmd: &mut Command,
+    output_prefix: &str,
+) -> std::io::Result<(ExitStatus, String)> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let (_, stderr) = stream_output(logger, &mut child, output_prefix, false)?;
+    Ok((child.wait()?, stderr))
+}
+
+/// Log what a running command writes to stderr line by line, as warnings, and what it
+/// writes to stdout unless it is captured. Returns the captured stdout and the stderr.
+fn stream_output(
+    logger: &Logger,
+    child: &mut Child,
+    output_prefix: &str,
+    capture_stdout: bool,
+) -> std::io::Result<(Vec<u8>, String)> {
+    let mut stdout = child.stdout.take().unwrap();
+    let stderr = child.stderr.take().unwrap();
+
+    let (stdout, stderr_lines) = crossbeam::scope(|s| {
+        let stdout = s.spawn(move |_| {
+            let mut captured = vec![];
+            if capture_stdout {
+                let _ = stdout.read_to_end(&mut captured);
+            } else {
+                for line in BufReader::new(stdout).lines().filter_map(Result::ok) {
+                    info!(logger, "{}{}", output_prefix, line);
+                }
+            }
+            captured
+        });
+        let mut lines = vec![];
+        for line in BufReader::new(stderr).lines().filter_map(Result::ok) {
+            warn!(logger, "{}{}", output_prefix, line);
+            lines.push(line);
+        }
+        (stdout.join().unwrap_or_default(), lines)
+    })
+    .map_err(|_| std::io::Error::new(ErrorKind::Other, "Cannot read the build output."))?;
+
+    Ok((stdout, stderr_lines.join("\n")))
+}
+
+/// Returns the canisters named in the `dependencies` field of a canister.
+fn declared_dependencies(pool: &CanisterPool, info: &CanisterInfo) -> DfxResult<Vec<CanisterId>> {
+    let deps = match info.get_extra_value("dependencies") {
+        None => vec![],
+        Some(v) => Vec::<String>::deserialize(v)
+            .map_err(|_| anyhow!("Field 'dependencies' is of the wrong type."))?,
+    };
+    deps.iter()
+        .map(|name| {
+            pool.get_first_canister_with_name(name)
+                .map(|c| c.canister_id())
+                .map_or_else(
+                    || {
+                        Err(anyhow!(
+                            "A canister with the name '{}' was not found in the current project.",
+                            name.clone()
+                        ))
+                    },
+                    DfxResult::Ok,
+                )
+        })
+        .collect::<DfxResult<Vec<CanisterId>>>()
+}
+
+/// Returns the inputs declared in the `inputs` field of a canister, relative to its
+/// workspace root. Inputs can be glob patterns, e.g. `src/**/*.py`.
+fn declared_inputs(info: &CanisterInfo) -> DfxResult<Vec<PathBuf>> {
+    let inputs = if info.has_extra("inputs") {
+        info.get_extra::<Vec<String>>("inputs")?
+    } else {
+        vec![]
+    };
+    let mut paths = vec![];
+    for input in inputs {
+        if is_glob(&input) {
+            paths.extend(expand_glob(info.get_workspace_root(), &input)?);
+        } else {
+            paths.push(info.get_workspace_root().join(input));
+        }
+    }
+    Ok(paths)
+}
+
+/// Returns the files under a directory matching a glob pattern, in order.
+fn expand_glob(root: &Path, pattern: &str) -> DfxResult<Vec<PathBuf>> {
+    let regex = glob_to_regex(pattern)?;
+
+    // Only walk the directory before the first wildcard.
+    let literal = &pattern[..pattern.find(|c| c == '*' || c == '?').unwrap_or(0)];
+    let base = match literal.rfind('/') {
+        Some(index) => root.join(&literal[..index]),
+        None => root.to_path_buf(),
+    };
+    if !base.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut paths = vec![];
+    for entry in WalkDir::new(base).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if let Ok(relative) = entry.path().strip_prefix(root) {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if regex.is_match(&relative) {
+                paths.push(entry.into_path());
+            }
+        }
+    }
+    Ok(paths)
+}
+
 #[derive(Clone)]
 pub struct BuildConfig {
     profile: Profile,
+    /// The name of the build profile.
+    pub profile_name: String,
+    /// Extra arguments passed to the compiler, keyed by canister type.
+    builder_args: BTreeMap<String, Vec<String>>,
+    /// Environment variables set for the build commands of custom canisters.
+    pub env: BTreeMap<String, String>,
     pub build_mode_check: bool,
+    /// Build every canister, even those that did not change since their last build.
+    pub force: bool,
+    /// The maximum number of canisters built at the same time.
+    pub jobs: usize,
     pub network_name: String,
 
     /// The root of all IDL files.
     pub idl_root: PathBuf,
     /// The root for all build files.
     pub build_root: PathBuf,
+    /// Where the Wasm and IDL files of the canisters built are copied, if anywhere.
+    pub output_root: Option<PathBuf>,
+    /// The names of all the canisters of the project, built or not.
+    pub canister_names: BTreeSet<String>,
+    /// The git revision of the project, or None if it is not in a git repository.
+    pub git_revision: Option<String>,
+
+    /// Whether to remove the custom sections of the Wasm modules built.
+    pub strip_wasm: bool,
+    /// The custom sections kept when stripping the Wasm modules.
+    pub keep_sections: Vec<String>,
+    /// The maximum size of a Wasm module, in bytes.
+    pub max_wasm_size: Option<u64>,
+
+    /// How the progress of the build is reported.
+    pub message_format: MessageFormat,
 }
 
 impl BuildConfig {
-    pub fn from_config(config: &Config) -> DfxResult<Self> {
+    /// Create the configuration of a build with a profile of the project, or its default
+    /// profile if none is given.
+    pub fn from_config(config: &Config, profile_name: Option<&str>) -> DfxResult<Self> {
         let config_intf = config.get_config();
+        config_intf.check_resolved("defaults.build")?;
         let network_name = get_network_context()?;
         let build_root = config.get_temp_path().join(&network_name);
         let build_root = build_root.join("canisters");
+        let (profile_name, profile) = config_intf.get_profile(profile_name)?;
+        let codegen = profile.codegen.unwrap_or(Profile::Debug);
+        let max_wasm_size = profile
+            .max_wasm_size
+            .as_ref()
+            .map(|size| match size.parse::<Bytes>() {
+                Ok(bytes) => Ok(bytes.size() as u64),
+                Err(_) => Err(anyhow!(
+                    "Invalid max_wasm_size '{}' in build profile '{}'.",
+                    size,
+                    profile_name
+                )),
+            })
+            .transpose()?;
 
         Ok(BuildConfig {
             network_name,
-            profile: config_intf.profile.unwrap_or(Profile::Debug),
+            profile: codegen,
+            profile_name,
+            builder_args: profile.args,
+            env: profile.env,
             build_mode_check: false,
+            force: false,
+            jobs: 1,
             build_root: build_root.clone(),
             idl_root: build_root.join("idl/"),
+            output_root: profile
+                .output
+                .map(|output| config.get_project_root().join(output)),
+            canister_names: config_intf
+                .canisters
+                .as_ref()
+                .map(|canisters| canisters.keys().cloned().collect())
+                .unwrap_or_default(),
+            git_revision: git_revision(config.get_project_root()),
+            strip_wasm: profile.strip.unwrap_or(matches!(codegen, Profile::Release)),
+            keep_sections: profile.keep_sections,
+            max_wasm_size,
+            message_format: MessageFormat::Human,
         })
     }
 
+    /// The extra arguments of the build profile for the compiler of a canister type.
+    pub fn get_builder_args(&self, canister_type: &str) -> &[String] {
+        self.builder_args
+            .get(canister_type)
+            .map_or(&[], |args| args.as_slice())
+    }
+
     pub fn with_build_mode_check(self, build_mode_check: bool) -> Self {
         Self {
             build_mode_check,
             ..self
         }
     }
+
+    /// Build in another build root, leaving the outputs of the project untouched.
+    pub fn with_build_root(self, build_root: &Path) -> Self {
+        Self {
+            build_root: build_root.to_path_buf(),
+            idl_root: build_root.join("idl/"),
+            output_root: None,
+            ..self
+        }
+    }
+
+    pub fn with_force(self, force: bool) -> Self {
+        Self { force, ..self }
+    }
+
+    pub fn with_message_format(self, message_format: MessageFormat) -> Self {
+        Self {
+            message_format,
+            ..self
+        }
+    }
+
+    pub fn with_jobs(self, jobs: usize) -> Self {
+        Self {
+            jobs: jobs.max(1),
+            ..self
+        }
+    }
+
+    /// The prefix of the lines of output of the build of a canister. When canisters are
+    /// built in parallel, each line is prefixed with the name of the canister.
+    pub fn get_output_prefix(&self, info: &CanisterInfo) -> String {
+        if self.jobs > 1 {
+            format!("[{}] ", info.get_name())
+        } else {
+            String::new()
+        }
+    }
+
+    /// A description of the build profile, for the fingerprint of a build.
+    pub fn get_profile_fingerprint(&self) -> String {
+        format!(
+            "{} {:?} {:?} {:?} {:?} {:?} {:?}",
+            self.profile_name,
+            self.profile,
+            self.builder_args,
+            self.env,
+            self.strip_wasm,
+            self.keep_sections,
+            self.max_wasm_size
+        )
+    }
 }
 
 pub struct BuilderPool {
     builders: Vec<Arc<dyn CanisterBuilder>>,
+    logger: Logger,
 }
 
 impl BuilderPool {
@@ -121,14 +455,66 @@ impl BuilderPool {
         builders.push(Arc::new(assets::AssetsBuilder::new(env)?));
         builders.push(Arc::new(custom::CustomBuilder::new(env)?));
         builders.push(Arc::new(motoko::MotokoBuilder::new(env)?));
+        builders.push(Arc::new(rust::RustBuilder::new(env)?));
 
-        Ok(Self { builders })
+        Ok(Self {
+            builders,
+            logger: env.get_logger().clone(),
+        })
     }
 
+    /// Returns the builder of a canister. Canisters of a type dfx does not know are built by
+    /// the `dfx-builder-<type>` plugin, if there is one in the PATH.
     pub fn get(&self, info: &CanisterInfo) -> Option<Arc<dyn CanisterBuilder>> {
-        self.builders
-            .iter()
-            .find(|builder| builder.supports(&info))
-            .map(|x| Arc::clone(x))
+        if let Some(builder) = self.builders.iter().find(|builder| builder.supports(&info)) {
+            return Some(Arc::clone(builder));
+        }
+        let executable = plugin::find_plugin(info.get_type())?;
+        let builder = plugin::PluginBuilder::new(&self.logger, executable);
+        if builder.supports(info) {
+            Some(Arc::new(builder))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in &[
+            "src/main.py",
+            "src/lib/util.py",
+            "src/lib/notes.txt",
+            "setup.py",
+        ] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let matches = |pattern: &str| -> Vec<String> {
+            expand_glob(dir.path(), pattern)
+                .unwrap()
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(dir.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        assert_eq!(matches("src/*.py"), vec!["src/main.py"]);
+        assert_eq!(
+            matches("src/**/*.py"),
+            vec!["src/lib/util.py", "src/main.py"]
+        );
+        assert_eq!(matches("*.py"), vec!["setup.py"]);
+        assert_eq!(matches("src/lib/????s.txt"), vec!["src/lib/notes.txt"]);
     }
 }
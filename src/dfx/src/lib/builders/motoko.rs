@@ -1,13 +1,15 @@
 use crate::config::cache::Cache;
 use crate::config::dfinity::Profile;
 use crate::lib::builders::{
-    BuildConfig, BuildOutput, CanisterBuilder, IdlBuildOutput, WasmBuildOutput,
+    canister_identifier, declared_inputs, prefix_lines, BuildConfig, BuildOutput, CanisterBuilder,
+    IdlBuildOutput, WasmBuildOutput,
 };
 use crate::lib::canister_info::motoko::MotokoCanisterInfo;
 use crate::lib::canister_info::CanisterInfo;
+use crate::lib::diagnostics::emit_diagnostics;
 use crate::lib::environment::Environment;
 use crate::lib::error::{BuildError, DfxError, DfxResult};
-use crate::lib::models::canister::CanisterPool;
+use crate::lib::models::canister::{Canister, CanisterPool};
 use crate::lib::package_arguments::{self, PackageArguments};
 use ic_types::principal::Principal as CanisterId;
 use slog::{info, o, trace, warn, Logger};
@@ -16,11 +18,17 @@ use std::convert::TryFrom;
 use std::iter::FromIterator;
 use std::path::{Path, PathBuf};
 use std::process::Output;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 pub struct MotokoBuilder {
     logger: slog::Logger,
     cache: Arc<dyn Cache>,
+    /// The imports of the main file of each canister, found once per build.
+    imports: Mutex<BTreeMap<PathBuf, Arc<BTreeSet<MotokoImport>>>>,
+    /// The package arguments of each package tool, found once per build.
+    package_arguments: Mutex<BTreeMap<Option<String>, Arc<PackageArguments>>>,
+    /// Whether moc writes the signature of stable variables, found on the first build.
+    stable_types: Mutex<Option<bool>>,
 }
 
 impl MotokoBuilder {
@@ -30,8 +38,119 @@ impl MotokoBuilder {
                 "module" => "motoko"
             }),
             cache: env.get_cache(),
+            imports: Mutex::new(BTreeMap::new()),
+            package_arguments: Mutex::new(BTreeMap::new()),
+            stable_types: Mutex::new(None),
         })
     }
+
+    fn get_imports(&self, main_path: &Path) -> DfxResult<Arc<BTreeSet<MotokoImport>>> {
+        if let Some(imports) = self.imports.lock().unwrap().get(main_path) {
+            return Ok(Arc::clone(imports));
+        }
+        let imports = Arc::new(find_imports(self.cache.as_ref(), main_path)?);
+        self.imports
+            .lock()
+            .unwrap()
+            .insert(main_path.to_path_buf(), Arc::clone(&imports));
+        Ok(imports)
+    }
+
+    fn get_package_arguments(&self, packtool: &Option<String>) -> DfxResult<Arc<PackageArguments>> {
+        if let Some(arguments) = self.package_arguments.lock().unwrap().get(packtool) {
+            return Ok(Arc::clone(arguments));
+        }
+        let arguments = Arc::new(package_arguments::load(self.cache.as_ref(), packtool)?);
+        self.package_arguments
+            .lock()
+            .unwrap()
+            .insert(packtool.clone(), Arc::clone(&arguments));
+        Ok(arguments)
+    }
+
+    fn get_stable_types(&self) -> DfxResult<bool> {
+        let mut stable_types = self.stable_types.lock().unwrap();
+        if stable_types.is_none() {
+            *stable_types = Some(supports_stable_types(self.cache.as_ref())?);
+        }
+        Ok(stable_types.unwrap())
+    }
+}
+
+/// Find the imports of a Motoko file and, recursively, of the files it imports.
+fn find_imports(cache: &dyn Cache, main_path: &Path) -> DfxResult<BTreeSet<MotokoImport>> {
+    fn find_deps_recursive(
+        cache: &dyn Cache,
+        file: &Path,
+        result: &mut BTreeSet<MotokoImport>,
+    ) -> DfxResult {
+        if result.contains(&MotokoImport::Relative(file.to_path_buf())) {
+            return Ok(());
+        }
+        result.insert(MotokoImport::Relative(file.to_path_buf()));
+
+        let output = cache
+            .get_binary_command("moc")?
+            .arg("--print-deps")
+            .arg(&file)
+            .output()?;
+
+        let output = String::from_utf8_lossy(&output.stdout);
+        for line in output.lines() {
+            let import = MotokoImport::try_from(line)?;
+            match import {
+                MotokoImport::Canister(_) => {
+                    result.insert(import);
+                }
+                MotokoImport::Relative(path) => {
+                    find_deps_recursive(cache, path.as_path(), result)?;
+                }
+                MotokoImport::Lib(_) => (),
+                MotokoImport::Ic(_) => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut result = BTreeSet::new();
+    find_deps_recursive(cache, main_path, &mut result)?;
+    Ok(result)
+}
+
+/// The canisters a canister can import, by alias (`import Api "canister:<alias>"`). The
+/// canisters of workspace members are imported by their `canister_identifier`. The canisters
+/// of the same workspace member can also be imported by their unqualified name, which takes
+/// precedence.
+fn actor_aliases<'a>(
+    pool: &'a CanisterPool,
+    info: &CanisterInfo,
+) -> BTreeMap<String, &'a Canister> {
+    let canisters = pool.get_canister_list();
+    let (qualified, unqualified): (Vec<&Canister>, Vec<&Canister>) = canisters
+        .iter()
+        .copied()
+        .partition(|canister| canister.get_name().contains('.'));
+
+    let mut aliases = BTreeMap::new();
+    for canister in unqualified {
+        aliases.insert(canister.get_name().to_string(), canister);
+    }
+    // A canister named like the alias of a qualified name keeps its name.
+    for canister in qualified {
+        aliases
+            .entry(canister_identifier(canister.get_name()))
+            .or_insert(canister);
+    }
+    if let Some(member) = info.get_workspace_member() {
+        let prefix = format!("{}.", member);
+        for canister in canisters {
+            if let Some(name) = canister.get_name().strip_prefix(&prefix) {
+                aliases.insert(name.to_string(), canister);
+            }
+        }
+    }
+    aliases
 }
 
 impl CanisterBuilder for MotokoBuilder {
@@ -40,52 +159,15 @@ impl CanisterBuilder for MotokoBuilder {
         pool: &CanisterPool,
         info: &CanisterInfo,
     ) -> DfxResult<Vec<CanisterId>> {
-        let mut result = BTreeSet::new();
         let motoko_info = info.as_info::<MotokoCanisterInfo>()?;
-
-        fn find_deps_recursive(
-            cache: &dyn Cache,
-            file: &Path,
-            result: &mut BTreeSet<MotokoImport>,
-        ) -> DfxResult {
-            if result.contains(&MotokoImport::Relative(file.to_path_buf())) {
-                return Ok(());
-            }
-
-            let output = cache
-                .get_binary_command("moc")?
-                .arg("--print-deps")
-                .arg(&file)
-                .output()?;
-
-            let output = String::from_utf8_lossy(&output.stdout);
-            for line in output.lines() {
-                let import = MotokoImport::try_from(line)?;
-                match import {
-                    MotokoImport::Canister(_) => {
-                        result.insert(import);
-                    }
-                    MotokoImport::Relative(path) => {
-                        find_deps_recursive(cache, path.as_path(), result)?;
-                    }
-                    MotokoImport::Lib(_) => (),
-                    MotokoImport::Ic(_) => (),
-                }
-            }
-
-            Ok(())
-        }
-        find_deps_recursive(
-            self.cache.as_ref(),
-            motoko_info.get_main_path(),
-            &mut result,
-        )?;
+        let result = self.get_imports(motoko_info.get_main_path())?;
+        let aliases = actor_aliases(pool, info);
 
         Ok(result
             .iter()
             .filter_map(|import| {
                 if let MotokoImport::Canister(name) = import {
-                    pool.get_first_canister_with_name(name)
+                    aliases.get(name)
                 } else {
                     None
                 }
@@ -94,10 +176,46 @@ impl CanisterBuilder for MotokoBuilder {
             .collect())
     }
 
+    /// The Motoko source files of the canister, the packages of the package tool and the
+    /// declared inputs. The base library comes with dfx.
+    fn get_inputs(
+        &self,
+        _pool: &CanisterPool,
+        info: &CanisterInfo,
+    ) -> DfxResult<Option<Vec<PathBuf>>> {
+        let motoko_info = info.as_info::<MotokoCanisterInfo>()?;
+        let mut inputs: Vec<PathBuf> = self
+            .get_imports(motoko_info.get_main_path())?
+            .iter()
+            .filter_map(|import| match import {
+                MotokoImport::Relative(path) => Some(path.clone()),
+                _ => None,
+            })
+            .collect();
+
+        if motoko_info.get_packtool().is_some() {
+            let package_arguments = self.get_package_arguments(motoko_info.get_packtool())?;
+            let stable_types = self.get_stable_types()?;
+            for arguments in package_arguments.windows(3) {
+                if arguments[0] == "--package" {
+                    inputs.push(PathBuf::from(&arguments[2]));
+                }
+            }
+        }
+
+        inputs.extend(declared_inputs(info)?);
+        Ok(Some(inputs))
+    }
+
     fn supports(&self, info: &CanisterInfo) -> bool {
         info.get_type() == "motoko"
     }
 
+    fn clear_cache(&self) {
+        self.imports.lock().unwrap().clear();
+        self.package_arguments.lock().unwrap().clear();
+    }
+
     fn build(
         &self,
         pool: &CanisterPool,
@@ -110,9 +228,9 @@ impl CanisterBuilder for MotokoBuilder {
         let output_wasm_path = motoko_info.get_output_wasm_path();
 
         let id_map = BTreeMap::from_iter(
-            pool.get_canister_list()
-                .iter()
-                .map(|c| (c.get_name().to_string(), c.canister_id().to_text())),
+            actor_aliases(pool, canister_info)
+                .into_iter()
+                .map(|(alias, canister)| (alias, canister.canister_id().to_text())),
         );
 
         std::fs::create_dir_all(motoko_info.get_output_root())?;
@@ -120,21 +238,29 @@ impl CanisterBuilder for MotokoBuilder {
         let idl_dir_path = &config.idl_root;
         std::fs::create_dir_all(&idl_dir_path)?;
 
-        let package_arguments =
-            package_arguments::load(cache.as_ref(), motoko_info.get_packtool())?;
+        let package_arguments = self.get_package_arguments(motoko_info.get_packtool())?;
 
         // Generate IDL
         let output_idl_path = motoko_info.get_output_idl_path();
+        let output_prefix = config.get_output_prefix(canister_info);
         let params = MotokoParams {
             build_target: BuildTarget::IDL,
+            stable_types,
             surpress_warning: false,
+            output_prefix: &output_prefix,
             input: &input_path,
             package_arguments: &package_arguments,
+            extra_arguments: config.get_builder_args("motoko"),
             output: &output_idl_path,
             idl_path: &idl_dir_path,
             idl_map: &id_map,
         };
-        motoko_compile(&self.logger, cache.as_ref(), &params)?;
+        let output = motoko_compile(&self.logger, cache.as_ref(), &params)?;
+        emit_diagnostics(
+            config.message_format,
+            canister_info.get_name(),
+            &String::from_utf8_lossy(&output.stderr),
+        );
 
         // Generate wasm
         let params = MotokoParams {
@@ -142,10 +268,13 @@ impl CanisterBuilder for MotokoBuilder {
                 Profile::Release => BuildTarget::Release,
                 _ => BuildTarget::Debug,
             },
+            stable_types,
             // Surpress the warnings the second time we call moc
             surpress_warning: true,
+            output_prefix: &output_prefix,
             input: &input_path,
             package_arguments: &package_arguments,
+            extra_arguments: config.get_builder_args("motoko"),
             output: &output_wasm_path,
             idl_path: &idl_dir_path,
             idl_map: &id_map,
@@ -172,13 +301,18 @@ enum BuildTarget {
 
 struct MotokoParams<'a> {
     build_target: BuildTarget,
+    /// Whether to write the signature of the stable variables next to the module.
+    stable_types: bool,
     idl_path: &'a Path,
     idl_map: &'a CanisterIdMap,
     package_arguments: &'a PackageArguments,
+    /// Arguments of the build profile.
+    extra_arguments: &'a [String],
     output: &'a Path,
     input: &'a Path,
     // The following fields are control flags for dfx and will not be used by self.to_args()
     surpress_warning: bool,
+    output_prefix: &'a str,
 }
 
 impl MotokoParams<'_> {
@@ -190,6 +324,9 @@ impl MotokoParams<'_> {
             BuildTarget::Debug => cmd.args(&["-c", "--debug"]),
             BuildTarget::IDL => cmd.arg("--idl"),
         };
+        if self.stable_types && !matches!(self.build_target, BuildTarget::IDL) {
+            cmd.arg("--stable-types");
+        }
         if !self.idl_map.is_empty() {
             cmd.arg("--actor-idl").arg(self.idl_path);
             for (name, canister_id) in self.idl_map.iter() {
@@ -197,15 +334,31 @@ impl MotokoParams<'_> {
             }
         };
         cmd.args(self.package_arguments);
+        cmd.args(self.extra_arguments);
     }
 }
 
+/// Whether the moc of the cache can write and compare the signatures of stable variables,
+/// with `--stable-types` and `--stable-compatible`. Older versions of moc cannot.
+pub fn supports_stable_types(cache: &dyn Cache) -> DfxResult<bool> {
+    let output = cache.get_binary_command("moc")?.arg("--help").output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).contains("--stable-types"))
+}
+
 /// Compile a motoko file.
-fn motoko_compile(logger: &Logger, cache: &dyn Cache, params: &MotokoParams<'_>) -> DfxResult {
+fn motoko_compile(
+    logger: &Logger,
+    cache: &dyn Cache,
+    params: &MotokoParams<'_>,
+) -> DfxResult<Output> {
     let mut cmd = cache.get_binary_command("moc")?;
     params.to_args(&mut cmd);
-    run_command(logger, &mut cmd, params.surpress_warning)?;
-    Ok(())
+    run_command(
+        logger,
+        &mut cmd,
+        params.surpress_warning,
+        params.output_prefix,
+    )
 }
 
 #[derive(Debug, PartialOrd, Ord, PartialEq, Eq)]
@@ -282,6 +435,7 @@ fn run_command(
     logger: &slog::Logger,
     cmd: &mut std::process::Command,
     surpress_warning: bool,
+    output_prefix: &str,
 ) -> DfxResult<Output> {
     trace!(logger, r#"Running {}..."#, format!("{:?}", cmd));
 
@@ -295,10 +449,10 @@ fn run_command(
         )))
     } else {
         if !output.stdout.is_empty() {
-            info!(logger, "{}", String::from_utf8_lossy(&output.stdout));
+            info!(logger, "{}", prefix_lines(output_prefix, &output.stdout));
         }
         if !surpress_warning && !output.stderr.is_empty() {
-            warn!(logger, "{}", String::from_utf8_lossy(&output.stderr));
+            warn!(logger, "{}", prefix_lines(output_prefix, &output.stderr));
         }
         Ok(output)
     }
@@ -0,0 +1,385 @@
+use crate::lib::builders::{
+    declared_inputs, set_canister_env, stream_output, BuildConfig, BuildOutput, CanisterBuilder,
+    IdlBuildOutput, WasmBuildOutput,
+};
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::error::DfxResult;
+use crate::lib::models::canister::CanisterPool;
+
+use anyhow::{anyhow, bail, Context};
+use ic_types::principal::Principal as CanisterId;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use slog::{warn, Logger};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// The version of the protocol spoken with builder plugins.
+const PROTOCOL_VERSION: u32 = 1;
+
+/// The prefix of the executables that build canisters of other types than those dfx knows,
+/// e.g. `dfx-builder-python` for canisters of type `python`.
+const PLUGIN_PREFIX: &str = "dfx-builder-";
+
+/// Returns the path of the builder plugin of a canister type, if there is one in the PATH.
+pub fn find_plugin(canister_type: &str) -> Option<PathBuf> {
+    let name = format!("{}{}", PLUGIN_PREFIX, canister_type);
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// The canister being built, as sent to a plugin.
+#[derive(Serialize)]
+struct PluginCanister<'a> {
+    name: &'a str,
+    #[serde(rename = "type")]
+    canister_type: &'a str,
+    /// Missing when asking whether the plugin supports the canister.
+    canister_id: Option<String>,
+    workspace_root: &'a Path,
+    /// The directory where dfx keeps the build output of the canister.
+    output_root: &'a Path,
+    /// The configuration of the canister in dfx.json, for the current network.
+    config: &'a BTreeMap<String, serde_json::Value>,
+}
+
+/// A dependency of the canister being built, as sent to a plugin.
+#[derive(Serialize)]
+struct PluginDependency {
+    canister_id: String,
+    /// The Candid interface of the dependency, if it was built.
+    candid: Option<PathBuf>,
+}
+
+/// The build settings, as sent to a plugin.
+#[derive(Serialize)]
+struct PluginBuildConfig<'a> {
+    profile: &'a str,
+    /// The arguments of the build profile for the type of the canister.
+    args: &'a [String],
+    env: &'a BTreeMap<String, String>,
+    network: &'a str,
+    build_root: &'a Path,
+    idl_root: &'a Path,
+    dependencies: BTreeMap<String, PluginDependency>,
+}
+
+#[derive(Serialize)]
+struct PluginRequest<'a> {
+    version: u32,
+    method: &'a str,
+    canister: PluginCanister<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config: Option<PluginBuildConfig<'a>>,
+}
+
+#[derive(Deserialize)]
+struct SupportsResponse {
+    supports: bool,
+}
+
+#[derive(Deserialize)]
+struct DependenciesResponse {
+    dependencies: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct EmptyResponse {}
+
+#[derive(Deserialize)]
+struct BuildResponse {
+    wasm: PathBuf,
+    candid: PathBuf,
+}
+
+/// A Builder for canisters of a type dfx does not know, which runs the executable
+/// `dfx-builder-<type>` found in the PATH. For every request, the plugin is run with the
+/// name of the method as its argument, reads a JSON request on stdin, and writes a JSON
+/// response on stdout:
+///   `supports`         => `{ "supports": true }`
+///   `get_dependencies` => `{ "dependencies": ["<canister name>", ...] }`
+///   `prebuild`         => `{}`
+///   `build`            => `{ "wasm": "<path>", "candid": "<path>" }`
+///   `postbuild`        => `{}`
+/// A plugin reports a failure with `{ "error": "<message>" }` or a non-zero exit code. What
+/// it writes to stderr is shown to the user as it is written. Relative paths are relative to the workspace
+/// root. The build request also sets the environment variables of custom canister builds.
+pub struct PluginBuilder {
+    logger: Logger,
+    executable: PathBuf,
+    /// The dependencies of each canister, asked once per build.
+    dependencies: Mutex<BTreeMap<String, Vec<CanisterId>>>,
+}
+
+impl PluginBuilder {
+    pub fn new(logger: &Logger, executable: PathBuf) -> Self {
+        PluginBuilder {
+            logger: logger.clone(),
+            executable,
+            dependencies: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn build_config<'a>(
+        &self,
+        pool: &CanisterPool,
+        info: &CanisterInfo,
+        config: &'a BuildConfig,
+    ) -> DfxResult<PluginBuildConfig<'a>> {
+        let mut dependencies = BTreeMap::new();
+        for dependency_id in self.get_dependencies(pool, info)? {
+            if let Some(dependency) = pool.get_canister(&dependency_id) {
+                let candid = dependency
+                    .get_build_output()
+                    .map(|output| match &output.idl {
+                        IdlBuildOutput::File(path) => path.clone(),
+                    });
+                dependencies.insert(
+                    dependency.get_name().to_string(),
+                    PluginDependency {
+                        canister_id: dependency_id.to_text(),
+                        candid,
+                    },
+                );
+            }
+        }
+        Ok(PluginBuildConfig {
+            profile: &config.profile_name,
+            args: config.get_builder_args(info.get_type()),
+            env: &config.env,
+            network: &config.network_name,
+            build_root: &config.build_root,
+            idl_root: &config.idl_root,
+            dependencies,
+        })
+    }
+
+    /// Run a method of the plugin and parse its response.
+    fn request<T: DeserializeOwned>(
+        &self,
+        info: &CanisterInfo,
+        method: &str,
+        config: Option<PluginBuildConfig<'_>>,
+        mut cmd: Command,
+        output_prefix: &str,
+    ) -> DfxResult<T> {
+        // The canister has no ID yet when the builder of the canister is chosen.
+        let canister_id = match method {
+            "supports" => None,
+            _ => Some(info.get_canister_id()?.to_text()),
+        };
+        let request = PluginRequest {
+            version: PROTOCOL_VERSION,
+            method,
+            canister: PluginCanister {
+                name: info.get_name(),
+                canister_type: info.get_type(),
+                canister_id,
+                workspace_root: info.get_workspace_root(),
+                output_root: info.get_output_root(),
+                config: info.get_extras(),
+            },
+            config,
+        };
+        let request = serde_json::to_vec(&request)?;
+
+        let mut child = cmd
+            .arg(method)
+            .current_dir(info.get_workspace_root())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| {
+                format!("Cannot run builder plugin '{}'.", self.executable.display())
+            })?;
+        child.stdin.take().unwrap().write_all(&request)?;
+        let (stdout, _) = stream_output(&self.logger, &mut child, output_prefix, true)?;
+        let status = child.wait()?;
+
+        let response: serde_json::Value = serde_json::from_slice(&stdout).map_err(|e| {
+            anyhow!(
+                "Builder plugin '{}' wrote an invalid response to '{}' ({}): {}",
+                self.executable.display(),
+                method,
+                status,
+                e
+            )
+        })?;
+        if let Some(error) = response.get("error") {
+            bail!(
+                "Builder plugin '{}' failed '{}' for canister {}: {}",
+                self.executable.display(),
+                method,
+                info.get_name(),
+                error
+                    .as_str()
+                    .map_or_else(|| error.to_string(), str::to_string)
+            );
+        }
+        if !status.success() {
+            bail!(
+                "Builder plugin '{}' failed '{}' for canister {} ({}).",
+                self.executable.display(),
+                method,
+                info.get_name(),
+                status
+            );
+        }
+        serde_json::from_value(response).map_err(|e| {
+            anyhow!(
+                "Builder plugin '{}' wrote an invalid response to '{}': {}",
+                self.executable.display(),
+                method,
+                e
+            )
+        })
+    }
+
+    fn command(&self) -> Command {
+        Command::new(&self.executable)
+    }
+}
+
+impl CanisterBuilder for PluginBuilder {
+    fn supports(&self, info: &CanisterInfo) -> bool {
+        match self.request::<SupportsResponse>(info, "supports", None, self.command(), "") {
+            Ok(response) => response.supports,
+            Err(e) => {
+                warn!(self.logger, "{:#}", e);
+                false
+            }
+        }
+    }
+
+    fn get_dependencies(
+        &self,
+        pool: &CanisterPool,
+        info: &CanisterInfo,
+    ) -> DfxResult<Vec<CanisterId>> {
+        if let Some(dependencies) = self.dependencies.lock().unwrap().get(info.get_name()) {
+            return Ok(dependencies.clone());
+        }
+        let response: DependenciesResponse =
+            self.request(info, "get_dependencies", None, self.command(), "")?;
+        let dependencies = response
+            .dependencies
+            .iter()
+            .map(|name| {
+                pool.get_first_canister_with_name(name)
+                    .map(|c| c.canister_id())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "A canister with the name '{}' was not found in the current project.",
+                            name
+                        )
+                    })
+            })
+            .collect::<DfxResult<Vec<CanisterId>>>()?;
+        self.dependencies
+            .lock()
+            .unwrap()
+            .insert(info.get_name().to_string(), dependencies.clone());
+        Ok(dependencies)
+    }
+
+    /// The declared inputs. A canister built by a plugin that declares no inputs is
+    /// always built.
+    fn get_inputs(
+        &self,
+        _pool: &CanisterPool,
+        info: &CanisterInfo,
+    ) -> DfxResult<Option<Vec<PathBuf>>> {
+        let inputs = declared_inputs(info)?;
+        Ok(if inputs.is_empty() {
+            None
+        } else {
+            Some(inputs)
+        })
+    }
+
+    fn clear_cache(&self) {
+        self.dependencies.lock().unwrap().clear();
+    }
+
+    fn prebuild(
+        &self,
+        pool: &CanisterPool,
+        info: &CanisterInfo,
+        config: &BuildConfig,
+    ) -> DfxResult {
+        let plugin_config = self.build_config(pool, info, config)?;
+        let prefix = config.get_output_prefix(info);
+        self.request::<EmptyResponse>(
+            info,
+            "prebuild",
+            Some(plugin_config),
+            self.command(),
+            &prefix,
+        )?;
+        Ok(())
+    }
+
+    fn build(
+        &self,
+        pool: &CanisterPool,
+        info: &CanisterInfo,
+        config: &BuildConfig,
+    ) -> DfxResult<BuildOutput> {
+        let canister_id = info.get_canister_id()?;
+        let dependencies = self.get_dependencies(pool, info)?;
+        let plugin_config = self.build_config(pool, info, config)?;
+
+        let mut cmd = self.command();
+        let candid = info.get_build_idl_path();
+        set_canister_env(&mut cmd, pool, config, &canister_id, &candid, &dependencies);
+        let prefix = config.get_output_prefix(info);
+        let response: BuildResponse =
+            self.request(info, "build", Some(plugin_config), cmd, &prefix)?;
+
+        let wasm = info.get_workspace_root().join(response.wasm);
+        let candid = info.get_workspace_root().join(response.candid);
+        if !wasm.exists() {
+            bail!(
+                "Cannot find the Wasm module of canister {} at '{}'.",
+                info.get_name(),
+                wasm.display()
+            );
+        }
+        if !candid.exists() {
+            bail!(
+                "Cannot find the Candid interface of canister {} at '{}'.",
+                info.get_name(),
+                candid.display()
+            );
+        }
+        Ok(BuildOutput {
+            canister_id,
+            wasm: WasmBuildOutput::File(wasm),
+            idl: IdlBuildOutput::File(candid),
+        })
+    }
+
+    fn postbuild(
+        &self,
+        pool: &CanisterPool,
+        info: &CanisterInfo,
+        config: &BuildConfig,
+    ) -> DfxResult {
+        let plugin_config = self.build_config(pool, info, config)?;
+        let prefix = config.get_output_prefix(info);
+        self.request::<EmptyResponse>(
+            info,
+            "postbuild",
+            Some(plugin_config),
+            self.command(),
+            &prefix,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use crate::lib::builders::{
+    declared_dependencies, declared_inputs, run_build_command, set_canister_env, BuildConfig,
+    BuildOutput, CanisterBuilder, IdlBuildOutput, WasmBuildOutput,
+};
+use crate::lib::canister_info::rust::{RustCanisterInfo, RUST_WASM_TARGET};
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::environment::Environment;
+use crate::lib::error::DfxResult;
+use crate::lib::models::canister::CanisterPool;
+
+use anyhow::{anyhow, bail, Context};
+use console::style;
+use ic_types::principal::Principal as CanisterId;
+use serde::Deserialize;
+use slog::info;
+use slog::Logger;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize)]
+struct CargoMetadata {
+    packages: Vec<CargoPackage>,
+    target_directory: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct CargoPackage {
+    name: String,
+    targets: Vec<CargoTarget>,
+}
+
+#[derive(Deserialize)]
+struct CargoTarget {
+    name: String,
+    crate_types: Vec<String>,
+}
+
+/// A Builder for a Rust canister, which builds a package of the cargo workspace for
+/// `wasm32-unknown-unknown`, always in release. Cargo gets the same environment variables as
+/// the build commands of custom canisters, and the arguments of the build profile for `rust`.
+pub struct RustBuilder {
+    logger: Logger,
+}
+
+impl RustBuilder {
+    pub fn new(env: &dyn Environment) -> DfxResult<Self> {
+        Ok(RustBuilder {
+            logger: env.get_logger().clone(),
+        })
+    }
+}
+
+impl CanisterBuilder for RustBuilder {
+    fn supports(&self, info: &CanisterInfo) -> bool {
+        info.get_type() == "rust"
+    }
+
+    fn get_dependencies(
+        &self,
+        pool: &CanisterPool,
+        info: &CanisterInfo,
+    ) -> DfxResult<Vec<CanisterId>> {
+        declared_dependencies(pool, info)
+    }
+
+    /// The declared inputs and the Candid file. A Rust canister that declares no inputs is
+    /// always built, and cargo decides what to recompile.
+    fn get_inputs(
+        &self,
+        _pool: &CanisterPool,
+        info: &CanisterInfo,
+    ) -> DfxResult<Option<Vec<PathBuf>>> {
+        let mut inputs = declared_inputs(info)?;
+        if inputs.is_empty() {
+            return Ok(None);
+        }
+        inputs.push(
+            info.get_workspace_root()
+                .join(info.get_extra::<PathBuf>("candid")?),
+        );
+        Ok(Some(inputs))
+    }
+
+    fn build(
+        &self,
+        pool: &CanisterPool,
+        info: &CanisterInfo,
+        config: &BuildConfig,
+    ) -> DfxResult<BuildOutput> {
+        let rust_info = info.as_info::<RustCanisterInfo>()?;
+        let package = rust_info.get_package();
+        let wasm = find_output_wasm_path(info.get_workspace_root(), package)?;
+        let candid = rust_info.get_output_idl_path().to_path_buf();
+        let canister_id = info.get_canister_id()?;
+        let dependencies = self.get_dependencies(pool, info)?;
+
+        // Rust canisters are always built in release, whatever the codegen of the build
+        // profile, as debug modules are too large and slow for the replica.
+        let mut args = vec![
+            "build".to_string(),
+            "--target".to_string(),
+            RUST_WASM_TARGET.to_string(),
+            "--release".to_string(),
+            "-p".to_string(),
+            package.to_string(),
+        ];
+        args.extend(config.get_builder_args("rust").iter().cloned());
+        info!(
+            self.logger,
+            r#"{} 'cargo {}'"#,
+            style("Executing").green().bold(),
+            args.join(" ")
+        );
+
+        let mut cmd = std::process::Command::new("cargo");
+        cmd.args(&args).current_dir(info.get_workspace_root());
+        set_canister_env(&mut cmd, pool, config, &canister_id, &candid, &dependencies);
+
+        let (status, _) =
+            run_build_command(&self.logger, &mut cmd, &config.get_output_prefix(info))
+                .context("Cannot run cargo. Is the Rust toolchain installed?")?;
+        if !status.success() {
+            bail!("Cargo failed to build package '{}' ({}).", package, status);
+        }
+        if !wasm.exists() {
+            bail!(
+                "Cannot find the Wasm module of package '{}' at '{}'.",
+                package,
+                wasm.display()
+            );
+        }
+        if !candid.exists() {
+            bail!(
+                "Cannot find the Candid interface of canister {} at '{}'.",
+                info.get_name(),
+                candid.display()
+            );
+        }
+
+        Ok(BuildOutput {
+            canister_id,
+            wasm: WasmBuildOutput::File(wasm),
+            idl: IdlBuildOutput::File(candid),
+        })
+    }
+}
+
+/// Ask cargo where the Wasm module of a package is built in release, as the target
+/// directory can be moved by the cargo workspace or CARGO_TARGET_DIR.
+fn find_output_wasm_path(workspace_root: &Path, package: &str) -> DfxResult<PathBuf> {
+    let output = std::process::Command::new("cargo")
+        .args(&["metadata", "--no-deps", "--format-version", "1"])
+        .current_dir(workspace_root)
+        .output()
+        .context("Cannot run cargo. Is the Rust toolchain installed?")?;
+    if !output.status.success() {
+        bail!(
+            "Cannot read the cargo metadata of package '{}':\n{}",
+            package,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let metadata: CargoMetadata = serde_json::from_slice(&output.stdout)?;
+
+    let cargo_package = metadata
+        .packages
+        .iter()
+        .find(|p| p.name == package)
+        .ok_or_else(|| anyhow!("Cannot find package '{}' in the cargo workspace.", package))?;
+    let target = cargo_package
+        .targets
+        .iter()
+        .find(|t| t.crate_types.iter().any(|c| c == "cdylib"))
+        .ok_or_else(|| {
+            anyhow!(
+                "Package '{}' has no library of crate type \"cdylib\".",
+                package
+            )
+        })?;
+    Ok(metadata
+        .target_directory
+        .join(RUST_WASM_TARGET)
+        .join("release")
+        .join(format!("{}.wasm", target.name.replace('-', "_"))))
+}
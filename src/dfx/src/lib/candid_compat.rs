@@ -0,0 +1,90 @@
+//! Checks that a new Candid interface of a canister does not break the clients of the
+//! interface it replaces.
+use crate::lib::error::DfxResult;
+use crate::util::check_candid;
+
+use candid::types::subtype::{subtype, Gamma};
+use candid::types::Type;
+
+/// Returns a description of every method of the old interface that clients can no longer
+/// call with the new interface: removed methods, and methods whose type is not a subtype
+/// of the old one. Returns nothing if the new interface is compatible.
+pub fn breaking_changes(old_candid: &str, new_candid: &str) -> DfxResult<Vec<String>> {
+    let (mut env, new_actor) = check_candid("new interface", new_candid)?;
+    let (old_env, old_actor) = check_candid("deployed interface", old_candid)?;
+    let old_actor = match old_actor {
+        Some(actor) => env.merge_type(old_env, actor),
+        // An interface without a service has no clients to break.
+        None => return Ok(vec![]),
+    };
+
+    let old_methods = env.as_service(&old_actor)?.to_vec();
+    let new_methods: Vec<(String, Type)> = match &new_actor {
+        Some(actor) => env.as_service(actor)?.to_vec(),
+        None => vec![],
+    };
+
+    let mut changes = vec![];
+    for (name, old_type) in old_methods {
+        match new_methods.iter().find(|(new_name, _)| *new_name == name) {
+            None => changes.push(format!(
+                "- {} : {}\n  The method was removed.",
+                name, old_type
+            )),
+            Some((_, new_type)) => {
+                let mut gamma = Gamma::new();
+                if let Err(e) = subtype(&mut gamma, &env, new_type, &old_type) {
+                    changes.push(format!(
+                        "- {} : {}\n+ {} : {}\n  {}",
+                        name, old_type, name, new_type, e
+                    ));
+                }
+            }
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = r#"
+        type Name = text;
+        service : {
+            greet : (Name) -> (text) query;
+            count : () -> (nat);
+        }
+    "#;
+
+    #[test]
+    fn adding_methods_is_compatible() {
+        let new = r#"
+            type Name = text;
+            service : {
+                greet : (Name) -> (text) query;
+                count : () -> (nat);
+                reset : () -> ();
+            }
+        "#;
+        assert!(breaking_changes(OLD, new).unwrap().is_empty());
+    }
+
+    #[test]
+    fn removed_and_changed_methods_are_breaking() {
+        let new = r#"
+            type Name = nat;
+            service : {
+                greet : (Name) -> (text) query;
+            }
+        "#;
+        let changes = breaking_changes(OLD, new).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert!(changes
+            .iter()
+            .any(|c| c.starts_with("- greet :") && c.contains("+ greet :")));
+        assert!(changes
+            .iter()
+            .any(|c| c.starts_with("- count :") && c.ends_with("The method was removed.")));
+    }
+}
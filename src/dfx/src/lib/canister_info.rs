@@ -3,6 +3,7 @@ use crate::config::dfinity::Config;
 use crate::lib::canister_info::assets::AssetsCanisterInfo;
 use crate::lib::canister_info::custom::CustomCanisterInfo;
 use crate::lib::canister_info::motoko::MotokoCanisterInfo;
+use crate::lib::canister_info::rust::RustCanisterInfo;
 use crate::lib::error::DfxResult;
 use crate::lib::provider::get_network_context;
 
@@ -14,6 +15,7 @@ use std::path::{Path, PathBuf};
 pub mod assets;
 pub mod custom;
 pub mod motoko;
+pub mod rust;
 
 pub trait CanisterInfoFactory {
     /// Returns true if this factory supports creating extra info for this canister info.
@@ -31,6 +33,7 @@ pub struct CanisterInfo {
     canister_type: String,
 
     workspace_root: PathBuf,
+    workspace_member: Option<String>,
     build_root: PathBuf,
     output_root: PathBuf,
     canister_root: PathBuf,
@@ -48,22 +51,24 @@ impl CanisterInfo {
         name: &str,
         canister_id: Option<CanisterId>,
     ) -> DfxResult<CanisterInfo> {
-        let workspace_root = config.get_path().parent().unwrap();
+        config.get_config().check_resolved("defaults.build")?;
         let build_defaults = config.get_config().get_defaults().get_build();
         let network_name = get_network_context()?;
         let build_root = config.get_temp_path().join(network_name);
         let build_root = build_root.join("canisters");
         std::fs::create_dir_all(&build_root)?;
 
-        let canister_map = (&config.get_config().canisters)
-            .as_ref()
-            .ok_or_else(|| anyhow!("No canisters in the configuration file."))?;
+        // The canister configuration, with the overrides of the current network applied.
+        let canister_config = config.get_config().get_canister_config(name)?;
 
-        let canister_config = canister_map
-            .get(name)
-            .ok_or_else(|| anyhow!("Cannot find canister '{}',", name.to_string()))?;
+        // The paths of a canister of a workspace member are relative to the member.
+        let workspace_root = match &canister_config.member {
+            Some(member) => member.root.clone(),
+            None => config.get_path().parent().unwrap().to_path_buf(),
+        };
+        let workspace_member = canister_config.member.as_ref().map(|m| m.name.clone());
 
-        let canister_root = workspace_root.to_path_buf();
+        let canister_root = workspace_root.clone();
         let extras = canister_config.extras.clone();
 
         let output_root = build_root.join(name);
@@ -78,7 +83,8 @@ impl CanisterInfo {
             name: name.to_string(),
             canister_type,
 
-            workspace_root: workspace_root.to_path_buf(),
+            workspace_root,
+            workspace_member,
             build_root,
             output_root,
             canister_root,
@@ -99,6 +105,10 @@ impl CanisterInfo {
     pub fn get_workspace_root(&self) -> &Path {
         &self.workspace_root
     }
+    /// The name of the workspace member that declares this canister, if any.
+    pub fn get_workspace_member(&self) -> Option<&str> {
+        self.workspace_member.as_deref()
+    }
     pub fn get_build_root(&self) -> &Path {
         &self.build_root
     }
@@ -143,6 +153,15 @@ impl CanisterInfo {
         &self.extras
     }
 
+    /// Move the build outputs of the canister to another build root.
+    pub fn with_build_root(self, build_root: &Path) -> Self {
+        CanisterInfo {
+            output_root: build_root.join(&self.name),
+            build_root: build_root.to_path_buf(),
+            ..self
+        }
+    }
+
     pub fn get_packtool(&self) -> &Option<String> {
         &self.packtool
     }
@@ -150,15 +169,21 @@ impl CanisterInfo {
     pub fn get_build_wasm_path(&self) -> PathBuf {
         self.build_root
             .join(PathBuf::from(&self.name))
-            .join(&self.name)
-            .with_extension("wasm")
+            .join(format!("{}.wasm", self.name))
     }
 
     pub fn get_build_idl_path(&self) -> PathBuf {
         self.build_root
             .join(PathBuf::from(&self.name))
-            .join(&self.name)
-            .with_extension("did")
+            .join(format!("{}.did", self.name))
+    }
+
+    /// The Candid interface of the module last installed on the network, kept to check that
+    /// the next upgrade does not break its clients.
+    pub fn get_deployed_idl_path(&self) -> PathBuf {
+        self.build_root
+            .join(PathBuf::from(&self.name))
+            .join("deployed.did")
     }
 
     pub fn get_output_wasm_path(&self) -> Option<PathBuf> {
@@ -168,6 +193,9 @@ impl CanisterInfo {
             Some(info.get_output_wasm_path().to_path_buf())
         } else if let Ok(info) = self.as_info::<AssetsCanisterInfo>() {
             Some(info.get_output_wasm_path().to_path_buf())
+        } else if RustCanisterInfo::supports(self) {
+            // Only the builder asks cargo where the module is built: the copy in the build root.
+            Some(self.get_build_wasm_path())
         } else {
             None
         }
@@ -180,8 +208,13 @@ impl CanisterInfo {
             Some(info.get_output_idl_path().to_path_buf())
         } else if let Ok(info) = self.as_info::<AssetsCanisterInfo>() {
             Some(info.get_output_idl_path().to_path_buf())
+        } else if RustCanisterInfo::supports(self) {
+            self.as_info::<RustCanisterInfo>()
+                .ok()
+                .map(|info| info.get_output_idl_path().to_path_buf())
         } else {
-            None
+            // Canisters built by plugins: the interface copied to the build root.
+            Some(self.get_build_idl_path())
         }
     }
 
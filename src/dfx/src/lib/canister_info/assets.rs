@@ -2,11 +2,27 @@ use crate::lib::canister_info::{CanisterInfo, CanisterInfoFactory};
 use crate::lib::error::DfxResult;
 
 use anyhow::bail;
+use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
+/// A rule of the `compression` of an assets canister in dfx.json, which chooses the
+/// content encodings stored for the assets matching a pattern.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CompressionRule {
+    /// A glob pattern matching the keys of the assets, e.g. `/fonts/**`. Patterns without
+    /// `/`, e.g. `*.wasm`, match the file names of the assets in any directory.
+    #[serde(rename = "match")]
+    pub pattern: String,
+    /// The content encodings stored besides identity: `gzip` and `br`.
+    pub encodings: Vec<String>,
+    /// The compression level of the encodings. By default, 6 for gzip and 11 for br.
+    pub level: Option<u32>,
+}
+
 pub struct AssetsCanisterInfo {
     input_root: PathBuf,
     source_paths: Vec<PathBuf>,
+    compression: Vec<CompressionRule>,
 
     output_wasm_path: PathBuf,
     output_idl_path: PathBuf,
@@ -26,6 +42,11 @@ impl AssetsCanisterInfo {
     pub fn get_output_assets_path(&self) -> &Path {
         self.output_assets_path.as_path()
     }
+    /// The rules choosing the content encodings of the assets. The first rule matching an
+    /// asset applies.
+    pub fn get_compression_rules(&self) -> &[CompressionRule] {
+        &self.compression
+    }
 
     pub fn assert_source_paths(&self) -> DfxResult<()> {
         let source_paths = &self.source_paths;
@@ -60,6 +81,11 @@ impl CanisterInfoFactory for AssetsCanisterInfo {
         } else {
             vec![]
         };
+        let compression = if info.has_extra("compression") {
+            info.get_extra::<Vec<CompressionRule>>("compression")?
+        } else {
+            vec![]
+        };
 
         let output_root = build_root.join(name);
 
@@ -70,6 +96,7 @@ impl CanisterInfoFactory for AssetsCanisterInfo {
         Ok(AssetsCanisterInfo {
             input_root,
             source_paths,
+            compression,
             output_wasm_path,
             output_idl_path,
             output_assets_path,
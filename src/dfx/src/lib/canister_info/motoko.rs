@@ -9,6 +9,7 @@ pub struct MotokoCanisterInfo {
 
     output_wasm_path: PathBuf,
     output_idl_path: PathBuf,
+    output_stable_types_path: PathBuf,
     output_did_js_path: PathBuf,
     output_canister_js_path: PathBuf,
     output_assets_root: PathBuf,
@@ -27,6 +28,15 @@ impl MotokoCanisterInfo {
     pub fn get_output_idl_path(&self) -> &Path {
         self.output_idl_path.as_path()
     }
+    /// The signature of the stable variables of the canister, written by moc.
+    pub fn get_output_stable_types_path(&self) -> &Path {
+        self.output_stable_types_path.as_path()
+    }
+    /// The signature of the stable variables of the module last installed on the network,
+    /// kept to check that the next upgrade can keep them.
+    pub fn get_deployed_stable_types_path(&self) -> PathBuf {
+        self.output_root.join("deployed.most")
+    }
     pub fn get_output_did_js_path(&self) -> &Path {
         self.output_did_js_path.as_path()
     }
@@ -64,8 +74,9 @@ impl CanisterInfoFactory for MotokoCanisterInfo {
 
         let input_path = workspace_root.join(&main_path);
         let output_root = build_root.join(name);
-        let output_wasm_path = output_root.join(name).with_extension("wasm");
+        let output_wasm_path = output_root.join(format!("{}.wasm", name));
         let output_idl_path = output_wasm_path.with_extension("did");
+        let output_stable_types_path = output_wasm_path.with_extension("most");
         let output_did_js_path = output_wasm_path.with_extension("did.js");
         let output_canister_js_path = output_wasm_path.with_extension("js");
         let output_assets_root = output_root.join("assets");
@@ -76,6 +87,7 @@ impl CanisterInfoFactory for MotokoCanisterInfo {
             idl_path,
             output_wasm_path,
             output_idl_path,
+            output_stable_types_path,
             output_did_js_path,
             output_canister_js_path,
             output_assets_root,
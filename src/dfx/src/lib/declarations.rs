@@ -0,0 +1,136 @@
+//! Client bindings of canisters, generated from their Candid interface by `dfx generate`.
+use crate::config::dfinity::BINDINGS;
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::error::DfxResult;
+use crate::util::check_candid_file;
+
+use anyhow::{bail, Context};
+use ic_types::principal::Principal as CanisterId;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+const INDEX_JS_TEMPLATE: &str = include_str!("../../assets/declarations/index.js");
+const INDEX_D_TS_TEMPLATE: &str = include_str!("../../assets/declarations/index.d.ts");
+
+/// The `declarations` of a canister in dfx.json.
+#[derive(Default, Deserialize)]
+pub struct DeclarationsConfig {
+    /// The directory of the bindings, relative to the project. Defaults to
+    /// `src/declarations/<canister name>`.
+    pub output: Option<PathBuf>,
+
+    /// The languages to generate bindings for. Defaults to JavaScript, TypeScript and
+    /// Candid.
+    pub bindings: Option<Vec<String>>,
+}
+
+impl DeclarationsConfig {
+    pub fn from_info(info: &CanisterInfo) -> DfxResult<Self> {
+        if info.has_extra("declarations") {
+            info.get_extra("declarations")
+        } else {
+            Ok(DeclarationsConfig::default())
+        }
+    }
+
+    pub fn get_output(&self, info: &CanisterInfo) -> PathBuf {
+        let output = match &self.output {
+            Some(output) => output.clone(),
+            None => Path::new("src/declarations").join(info.get_name()),
+        };
+        info.get_workspace_root().join(output)
+    }
+
+    pub fn get_bindings(&self) -> DfxResult<Vec<String>> {
+        let bindings = match &self.bindings {
+            Some(bindings) => bindings.clone(),
+            None => vec!["js".to_string(), "ts".to_string(), "did".to_string()],
+        };
+        if let Some(unknown) = bindings.iter().find(|b| !BINDINGS.contains(&b.as_str())) {
+            bail!(
+                "Unknown binding '{}'. The bindings are {}.",
+                unknown,
+                BINDINGS.join(", ")
+            );
+        }
+        Ok(bindings)
+    }
+}
+
+/// The name of the canister as a JavaScript identifier and environment variable.
+fn identifier(name: &str) -> String {
+    name.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
+
+/// Write the bindings of a built canister, and an actor factory for JavaScript and
+/// TypeScript. The factory uses the canister ID of the network the bindings are generated
+/// for, if the canister was created there. Returns the directory of the bindings.
+pub fn generate_declarations(
+    info: &CanisterInfo,
+    canister_id: Option<&CanisterId>,
+    is_ic: bool,
+) -> DfxResult<PathBuf> {
+    let config = DeclarationsConfig::from_info(info)?;
+    let output = config.get_output(info);
+    let bindings = config.get_bindings()?;
+    let name = info.get_name();
+
+    let idl_path = info.get_build_idl_path();
+    let (env, ty) = check_candid_file(&idl_path)?;
+    std::fs::create_dir_all(&output)
+        .with_context(|| format!("Cannot create directory at '{}'.", output.display()))?;
+
+    for binding in &bindings {
+        match binding.as_str() {
+            "js" => {
+                let content = candid::bindings::javascript::compile(&env, &ty);
+                std::fs::write(output.join(format!("{}.did.js", name)), content)?;
+            }
+            "ts" => {
+                let content = candid::bindings::typescript::compile(&env, &ty);
+                std::fs::write(output.join(format!("{}.did.d.ts", name)), content)?;
+            }
+            "did" => {
+                std::fs::copy(&idl_path, output.join(format!("{}.did", name)))?;
+            }
+            "mo" => {
+                let content = candid::bindings::motoko::compile(&env, &ty);
+                std::fs::write(output.join(format!("{}.mo", name)), content)?;
+            }
+            "rs" => {
+                let content = candid::bindings::rust::compile(&env, &ty);
+                std::fs::write(output.join(format!("{}.rs", name)), content)?;
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    let fill = |template: &str| {
+        template
+            .replace("{canister_name}", name)
+            .replace("{canister_ident}", &identifier(name))
+            .replace(
+                "{canister_id}",
+                &canister_id.map(|id| id.to_text()).unwrap_or_default(),
+            )
+            .replace("{fetch_root_key}", if is_ic { "false" } else { "true" })
+    };
+    if bindings.iter().any(|b| b == "js") {
+        std::fs::write(output.join("index.js"), fill(INDEX_JS_TEMPLATE))?;
+    }
+    if bindings.iter().any(|b| b == "ts") {
+        std::fs::write(output.join("index.d.ts"), fill(INDEX_D_TS_TEMPLATE))?;
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifiers_are_valid_in_javascript() {
+        assert_eq!(identifier("hello"), "hello");
+        assert_eq!(identifier("my-app.backend"), "my_app_backend");
+    }
+}
@@ -0,0 +1,254 @@
+//! Compiler diagnostics, and the events written by `dfx build --message-format json`.
+use crate::lib::error::{BuildError, DfxError};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+
+lazy_static! {
+    /// `src/main.mo:3.5-3.10: type error [M0057], unbound variable x`, as written by moc.
+    static ref MOC_DIAGNOSTIC: Regex = Regex::new(
+        r"^(?P<file>[^:\s][^:]*):(?P<line>\d+)\.(?P<column>\d+)-(?P<end_line>\d+)\.(?P<end_column>\d+): (?P<severity>[a-z ]+?)(?: \[(?P<code>\w+)\])?, (?P<message>.*)$"
+    )
+    .unwrap();
+
+    /// `src/main.c:3:5: error: unknown type name 'x'`, as written by most other compilers.
+    static ref COMMON_DIAGNOSTIC: Regex = Regex::new(
+        r"^(?P<file>[^:\s][^:]*):(?P<line>\d+):(?P<column>\d+): (?P<severity>fatal error|error|warning|note|info)(?:\[(?P<code>\w+)\])?: (?P<message>.*)$"
+    )
+    .unwrap();
+}
+
+/// How `dfx build` reports the progress of a build.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MessageFormat {
+    /// Log messages, for people.
+    Human,
+    /// One JSON object per line on stdout, for IDEs and CI. Log messages still go to stderr.
+    Json,
+}
+
+impl MessageFormat {
+    pub fn from_name(format: &str) -> Self {
+        match format {
+            "json" => MessageFormat::Json,
+            _ => MessageFormat::Human,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Position {
+    /// Starting at 1.
+    pub line: u32,
+    /// Starting at 1.
+    pub column: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// An error or warning of a compiler about a source file.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub range: Range,
+    pub severity: Severity,
+    pub code: Option<String>,
+    pub message: String,
+}
+
+/// An event of a build, written as one line of JSON with `--message-format json`.
+#[derive(Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum BuildEvent<'a> {
+    CanisterStarted {
+        canister: &'a str,
+    },
+    Diagnostic {
+        canister: &'a str,
+        #[serde(flatten)]
+        diagnostic: &'a Diagnostic,
+    },
+    CanisterFinished {
+        canister: &'a str,
+        success: bool,
+        /// Whether the output of the last build was reused, as nothing changed.
+        fresh: bool,
+        error: Option<String>,
+    },
+    BuildFinished {
+        success: bool,
+    },
+}
+
+fn severity(severity: &str) -> Severity {
+    match severity {
+        "warning" => Severity::Warning,
+        "note" | "info" => Severity::Info,
+        // moc has "type error", "syntax error", "import error"...
+        _ => Severity::Error,
+    }
+}
+
+fn number(captures: &regex::Captures<'_>, name: &str) -> u32 {
+    captures[name].parse().unwrap_or(0)
+}
+
+/// Parse the diagnostics in the output of a compiler. The lines following a diagnostic, up
+/// to the next one or an empty line, are part of its message.
+pub fn parse_diagnostics(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    let mut in_diagnostic = false;
+    for line in output.lines() {
+        let diagnostic = if let Some(captures) = MOC_DIAGNOSTIC.captures(line) {
+            Some(Diagnostic {
+                file: PathBuf::from(&captures["file"]),
+                range: Range {
+                    start: Position {
+                        line: number(&captures, "line"),
+                        column: number(&captures, "column"),
+                    },
+                    end: Position {
+                        line: number(&captures, "end_line"),
+                        column: number(&captures, "end_column"),
+                    },
+                },
+                severity: severity(&captures["severity"]),
+                code: captures.name("code").map(|code| code.as_str().to_string()),
+                message: captures["message"].to_string(),
+            })
+        } else if let Some(captures) = COMMON_DIAGNOSTIC.captures(line) {
+            let position = Position {
+                line: number(&captures, "line"),
+                column: number(&captures, "column"),
+            };
+            Some(Diagnostic {
+                file: PathBuf::from(&captures["file"]),
+                range: Range {
+                    start: position.clone(),
+                    end: position,
+                },
+                severity: severity(&captures["severity"]),
+                code: captures.name("code").map(|code| code.as_str().to_string()),
+                message: captures["message"].to_string(),
+            })
+        } else {
+            None
+        };
+
+        match (diagnostic, diagnostics.last_mut()) {
+            (Some(diagnostic), _) => {
+                diagnostics.push(diagnostic);
+                in_diagnostic = true;
+            }
+            (None, Some(last)) if in_diagnostic && !line.trim().is_empty() => {
+                last.message.push('\n');
+                last.message.push_str(line.trim_end());
+            }
+            (None, _) => in_diagnostic = false,
+        }
+    }
+    diagnostics
+}
+
+/// Returns what the tool that failed a build wrote to stderr, if the build failed because
+/// of a tool.
+pub fn tool_stderr(error: &DfxError) -> Option<&str> {
+    match error.downcast_ref::<BuildError>()? {
+        BuildError::CommandError(_, _, _, stderr) => Some(stderr),
+        BuildError::CustomToolError(_, stderr) => Some(stderr),
+        _ => None,
+    }
+}
+
+/// Write an event of a build to stdout, if the events are requested.
+pub fn emit(format: MessageFormat, event: &BuildEvent<'_>) {
+    if format != MessageFormat::Json {
+        return;
+    }
+    if let Ok(line) = serde_json::to_string(event) {
+        // Events of builds running in parallel must not interleave.
+        let stdout = std::io::stdout();
+        let mut stdout = stdout.lock();
+        let _ = writeln!(stdout, "{}", line);
+    }
+}
+
+/// Write the diagnostics in the output of a tool building a canister, if the events are
+/// requested.
+pub fn emit_diagnostics(format: MessageFormat, canister: &str, output: &str) {
+    if format != MessageFormat::Json {
+        return;
+    }
+    for diagnostic in parse_diagnostics(output) {
+        emit(
+            format,
+            &BuildEvent::Diagnostic {
+                canister,
+                diagnostic: &diagnostic,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_moc_diagnostics() {
+        let output = "src/main.mo:3.5-3.10: type error [M0057], unbound variable x\n\
+                      src/main.mo:5.1-6.2: warning [M0145], this pattern of type\n  \
+                      Nat\n\
+                      does not cover value\n  \
+                      0\n\
+                      \n\
+                      Done.\n";
+        let diagnostics = parse_diagnostics(output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].file, PathBuf::from("src/main.mo"));
+        assert_eq!(
+            diagnostics[0].range,
+            Range {
+                start: Position { line: 3, column: 5 },
+                end: Position {
+                    line: 3,
+                    column: 10
+                },
+            }
+        );
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("M0057"));
+        assert_eq!(diagnostics[0].message, "unbound variable x");
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(
+            diagnostics[1].message,
+            "this pattern of type\n  Nat\ndoes not cover value\n  0"
+        );
+    }
+
+    #[test]
+    fn parses_common_diagnostics() {
+        let diagnostics = parse_diagnostics("lib/main.c:12:3: error: expected ';'\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].file, PathBuf::from("lib/main.c"));
+        assert_eq!(diagnostics[0].range.start, diagnostics[0].range.end);
+        assert_eq!(diagnostics[0].range.start.line, 12);
+        assert_eq!(diagnostics[0].code, None);
+        assert_eq!(diagnostics[0].message, "expected ';'");
+    }
+}
@@ -100,6 +100,9 @@ impl EnvironmentImpl {
         // If any of those are empty string, we stop the fallback and use the current version.
         // If any of those are a valid version, we try to use that directly as is.
         // If any of those are an invalid version, we will show an error to the user.
+        if let Some(c) = &config {
+            c.get_config().check_resolved("dfx")?;
+        }
         let version = match std::env::var("DFX_VERSION") {
             Err(_) => match &config {
                 None => dfx_version().clone(),
@@ -205,8 +208,12 @@ impl Environment for EnvironmentImpl {
         }
     }
 
-    fn new_progress(&self, _message: &str) -> ProgressBar {
-        ProgressBar::discard()
+    fn new_progress(&self, message: &str) -> ProgressBar {
+        if self.progress {
+            ProgressBar::new_progress(message)
+        } else {
+            ProgressBar::discard()
+        }
     }
 
     fn get_selected_identity(&self) -> Option<&String> {
@@ -30,6 +30,9 @@ pub enum BuildError {
     #[error("The JavaScript bindings generator failed: {0}")]
     JsBindGenError(String),
 
-    #[error("The custom tool failed.")]
-    CustomToolError(Option<i32>),
+    #[error(
+        "The custom tool failed ({}):\n{1}",
+        .0.map_or("terminated by a signal".to_string(), |code| format!("exit code {}", code))
+    )]
+    CustomToolError(Option<i32>, String),
 }
@@ -0,0 +1,169 @@
+//! The `.ic-assets.json` files of the assets of a canister, which set the properties of the
+//! assets matching glob patterns.
+use crate::lib::error::DfxResult;
+use crate::util::glob::glob_to_regex;
+
+use anyhow::{anyhow, Context};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+use walkdir::WalkDir;
+
+pub const ASSETS_CONFIG_FILE_NAME: &str = ".ic-assets.json";
+
+/// A rule of an `.ic-assets.json` file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AssetRule {
+    /// A glob pattern matching the paths of the assets, relative to the directory of the
+    /// file. Patterns without `/` match the file names of the assets in any directory.
+    #[serde(rename = "match")]
+    pattern: String,
+    content_type: Option<String>,
+    encodings: Option<Vec<String>>,
+    ignore: Option<bool>,
+}
+
+/// The properties of an asset, from the rules matching it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AssetProperties {
+    /// Replaces the content type guessed from the file name.
+    pub content_type: Option<String>,
+    /// Replaces the content encodings chosen by the compression rules of dfx.json.
+    pub encodings: Option<Vec<String>>,
+    /// The asset is not uploaded.
+    pub ignore: bool,
+}
+
+struct CompiledRule {
+    /// The directory of the `.ic-assets.json` file, relative to the assets, with a trailing
+    /// `/` unless it is the root.
+    directory: String,
+    pattern: Regex,
+    file_name_only: bool,
+    rule: AssetRule,
+}
+
+#[derive(Default)]
+pub struct AssetsConfig {
+    rules: Vec<CompiledRule>,
+}
+
+impl AssetsConfig {
+    /// Load the `.ic-assets.json` files of a directory of assets. The rules of a file apply
+    /// to the assets of its directory and subdirectories. All the rules matching an asset
+    /// apply, those of deeper directories and later rules taking precedence.
+    pub fn load(root: &Path) -> DfxResult<Self> {
+        let mut files = vec![];
+        for entry in WalkDir::new(root) {
+            let entry = entry?;
+            if entry.file_type().is_file() && entry.file_name() == ASSETS_CONFIG_FILE_NAME {
+                files.push(entry.into_path());
+            }
+        }
+        files.sort_by_key(|path| path.components().count());
+
+        let mut rules = vec![];
+        for path in files {
+            let content = std::fs::read(&path)
+                .with_context(|| format!("Cannot read file at '{}'.", path.display()))?;
+            let file_rules: Vec<AssetRule> = serde_json::from_slice(&content)
+                .map_err(|e| anyhow!("Cannot parse the rules in '{}': {}", path.display(), e))?;
+            let directory = path
+                .parent()
+                .and_then(|dir| dir.strip_prefix(root).ok())
+                .map(|dir| dir.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+            let directory = if directory.is_empty() {
+                directory
+            } else {
+                format!("{}/", directory)
+            };
+            for rule in file_rules {
+                rules.push(CompiledRule {
+                    directory: directory.clone(),
+                    pattern: glob_to_regex(rule.pattern.trim_start_matches('/'))?,
+                    file_name_only: !rule.pattern.contains('/'),
+                    rule,
+                });
+            }
+        }
+        Ok(AssetsConfig { rules })
+    }
+
+    /// Returns the properties of the asset with a key, e.g. `/images/logo.png`.
+    pub fn get_properties(&self, key: &str) -> AssetProperties {
+        let path = key.trim_start_matches('/');
+        let mut properties = AssetProperties::default();
+        for compiled in &self.rules {
+            let relative = match path.strip_prefix(&compiled.directory) {
+                Some(relative) => relative,
+                None => continue,
+            };
+            let candidate = if compiled.file_name_only {
+                relative.rsplit('/').next().unwrap_or(relative)
+            } else {
+                relative
+            };
+            if !compiled.pattern.is_match(candidate) {
+                continue;
+            }
+
+            let rule = &compiled.rule;
+            if rule.content_type.is_some() {
+                properties.content_type = rule.content_type.clone();
+            }
+            if rule.encodings.is_some() {
+                properties.encodings = rule.encodings.clone();
+            }
+            if let Some(ignore) = rule.ignore {
+                properties.ignore = ignore;
+            }
+        }
+        properties
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_of_deeper_directories_take_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("docs")).unwrap();
+        std::fs::write(
+            dir.path().join(ASSETS_CONFIG_FILE_NAME),
+            r#"[
+              { "match": "**/*", "encodings": ["gzip"] },
+              { "match": "*.wasm", "content_type": "application/wasm", "encodings": ["br"] }
+            ]"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("docs").join(ASSETS_CONFIG_FILE_NAME),
+            r#"[
+              { "match": "*", "content_type": "text/plain" },
+              { "match": "drafts/**", "ignore": true }
+            ]"#,
+        )
+        .unwrap();
+        let config = AssetsConfig::load(dir.path()).unwrap();
+
+        let index = config.get_properties("/index.html");
+        assert_eq!(index.encodings, Some(vec!["gzip".to_string()]));
+        assert_eq!(index.content_type, None);
+        assert!(!index.ignore);
+
+        let wasm = config.get_properties("/app/main.wasm");
+        assert_eq!(wasm.content_type.as_deref(), Some("application/wasm"));
+        assert_eq!(wasm.encodings, Some(vec!["br".to_string()]));
+
+        let guide = config.get_properties("/docs/guide.html");
+        assert_eq!(guide.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(guide.encodings, Some(vec!["gzip".to_string()]));
+
+        assert!(config.get_properties("/docs/drafts/a.html").ignore);
+        assert!(!config.get_properties("/drafts/a.html").ignore);
+    }
+}
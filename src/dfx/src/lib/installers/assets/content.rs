@@ -1,6 +1,7 @@
 use crate::lib::error::DfxResult;
 
 use crate::lib::installers::assets::content_encoder::ContentEncoder;
+use brotli2::write::BrotliEncoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use mime::Mime;
@@ -17,22 +18,33 @@ impl Content {
     pub fn load(path: &Path) -> DfxResult<Content> {
         let data = std::fs::read(path)?;
 
-        // todo: check contents if mime_guess fails https://github.com/dfinity/sdk/issues/1594
         let media_type = mime_guess::from_path(path)
             .first()
+            .or_else(|| sniff_media_type(&data))
             .unwrap_or(mime::APPLICATION_OCTET_STREAM);
 
         Ok(Content { data, media_type })
     }
 
-    pub fn encode(&self, encoder: &ContentEncoder) -> DfxResult<Content> {
+    pub fn encode(&self, encoder: &ContentEncoder, level: u32) -> DfxResult<Content> {
         match encoder {
-            ContentEncoder::Gzip => self.to_gzip(),
+            ContentEncoder::Gzip => self.to_gzip(level),
+            ContentEncoder::Brotli => self.to_brotli(level),
         }
     }
 
-    pub fn to_gzip(&self) -> DfxResult<Content> {
-        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+    pub fn to_gzip(&self, level: u32) -> DfxResult<Content> {
+        let mut e = GzEncoder::new(Vec::new(), Compression::new(level));
+        e.write_all(&self.data)?;
+        let data = e.finish()?;
+        Ok(Content {
+            data,
+            media_type: self.media_type.clone(),
+        })
+    }
+
+    pub fn to_brotli(&self, level: u32) -> DfxResult<Content> {
+        let mut e = BrotliEncoder::new(Vec::new(), level);
         e.write_all(&self.data)?;
         let data = e.finish()?;
         Ok(Content {
@@ -47,3 +59,123 @@ impl Content {
         sha256.finish().to_vec()
     }
 }
+
+/// Guess the media type of content from its first bytes, for files whose name does not tell,
+/// e.g. `_redirects`, `LICENSE` or hashed bundles.
+pub fn sniff_media_type(data: &[u8]) -> Option<Mime> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"wOF2", "font/woff2"),
+        (b"wOFF", "font/woff"),
+        (b"\0asm", "application/wasm"),
+    ];
+    if let Some((_, media_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+    {
+        return media_type.parse().ok();
+    }
+    if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return "image/webp".parse().ok();
+    }
+
+    let text = std::str::from_utf8(data).ok()?;
+    let text = text.trim_start_matches('\u{feff}');
+    if text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c' | '\x1b'))
+    {
+        return None;
+    }
+    let start = text.trim_start();
+    let head: String = start.chars().take(256).collect::<String>().to_lowercase();
+    if head.starts_with("<!doctype html")
+        || head.starts_with("<html")
+        || head.starts_with("<head")
+        || head.starts_with("<body")
+    {
+        Some(mime::TEXT_HTML)
+    } else if head.starts_with("<svg")
+        || ((head.starts_with("<?xml") || head.starts_with("<!doctype svg"))
+            && head.contains("<svg"))
+    {
+        Some(mime::IMAGE_SVG)
+    } else if (start.starts_with('{') || start.starts_with('['))
+        && serde_json::from_str::<serde::de::IgnoredAny>(start).is_ok()
+    {
+        Some(mime::APPLICATION_JSON)
+    } else {
+        Some(mime::TEXT_PLAIN)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniff(data: &[u8]) -> Option<String> {
+        sniff_media_type(data).map(|media_type| media_type.to_string())
+    }
+
+    #[test]
+    fn sniffs_binary_signatures() {
+        assert_eq!(
+            sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").as_deref(),
+            Some("image/png")
+        );
+        assert_eq!(
+            sniff(b"\xff\xd8\xff\xe0\0\x10JFIF").as_deref(),
+            Some("image/jpeg")
+        );
+        assert_eq!(sniff(b"GIF87a\x01\0").as_deref(), Some("image/gif"));
+        assert_eq!(sniff(b"GIF89a\x01\0").as_deref(), Some("image/gif"));
+        assert_eq!(
+            sniff(b"RIFF\x24\0\0\0WEBPVP8 ").as_deref(),
+            Some("image/webp")
+        );
+        assert_eq!(sniff(b"wOF2\0\x01\0\0").as_deref(), Some("font/woff2"));
+        assert_eq!(
+            sniff(b"\0asm\x01\0\0\0").as_deref(),
+            Some("application/wasm")
+        );
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WAVEfmt "), None);
+        assert_eq!(sniff(b"\x7fELF\x02\x01\x01\0"), None);
+    }
+
+    #[test]
+    fn sniffs_text() {
+        assert_eq!(
+            sniff(b"\n<!DOCTYPE html>\n<html><body></body></html>").as_deref(),
+            Some("text/html")
+        );
+        assert_eq!(sniff(b"<html lang=\"en\">").as_deref(), Some("text/html"));
+        assert_eq!(
+            sniff(b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>").as_deref(),
+            Some("image/svg+xml")
+        );
+        assert_eq!(
+            sniff(b"<?xml version=\"1.0\"?>\n<svg></svg>").as_deref(),
+            Some("image/svg+xml")
+        );
+        assert_eq!(
+            sniff(b"{ \"name\": \"app\", \"files\": [1, 2] }").as_deref(),
+            Some("application/json")
+        );
+        assert_eq!(sniff(b"[1, 2, 3]\n").as_deref(), Some("application/json"));
+        assert_eq!(
+            sniff(b"/*  /index.html  200\n").as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(
+            sniff("\u{feff}Copyright \u{a9} 2021".as_bytes()).as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(sniff(b"[not json").as_deref(), Some("text/plain"));
+        assert_eq!(sniff(b"").as_deref(), Some("text/plain"));
+        assert_eq!(sniff(b"text\0with a nul"), None);
+        assert_eq!(sniff(b"\xc3\x28 invalid utf-8"), None);
+    }
+}
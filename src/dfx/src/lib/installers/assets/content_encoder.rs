@@ -1,11 +1,45 @@
+use crate::lib::error::DfxResult;
+
+use anyhow::bail;
+
+#[derive(Clone, Copy)]
 pub enum ContentEncoder {
     Gzip,
+    Brotli,
+}
+
+impl ContentEncoder {
+    /// Returns the encoder of a content encoding, e.g. `gzip` or `br`.
+    pub fn from_name(name: &str) -> DfxResult<Self> {
+        match name {
+            "gzip" => Ok(ContentEncoder::Gzip),
+            "br" => Ok(ContentEncoder::Brotli),
+            _ => bail!("Unknown content encoding '{}'. Use gzip or br.", name),
+        }
+    }
+
+    /// The highest compression level of the encoder.
+    pub fn max_level(&self) -> u32 {
+        match &self {
+            ContentEncoder::Gzip => 9,
+            ContentEncoder::Brotli => 11,
+        }
+    }
+
+    /// The compression level used when none is configured.
+    pub fn default_level(&self) -> u32 {
+        match &self {
+            ContentEncoder::Gzip => 6,
+            ContentEncoder::Brotli => 11,
+        }
+    }
 }
 
 impl std::fmt::Display for ContentEncoder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
             ContentEncoder::Gzip => f.write_str("gzip"),
+            ContentEncoder::Brotli => f.write_str("br"),
         }
     }
 }
@@ -1,30 +1,43 @@
-use crate::lib::canister_info::assets::AssetsCanisterInfo;
+use crate::lib::canister_info::assets::{AssetsCanisterInfo, CompressionRule};
 use crate::lib::canister_info::CanisterInfo;
+use crate::lib::environment::Environment;
 use crate::lib::error::{DfxError, DfxResult};
+use crate::lib::installers::assets::config::{AssetProperties, AssetsConfig};
 use crate::lib::installers::assets::content::Content;
 use crate::lib::installers::assets::content_encoder::ContentEncoder;
+use crate::lib::progress_bar::ProgressBar;
 use crate::lib::waiter::waiter_with_timeout;
+use crate::util::glob::glob_to_regex;
 use candid::{CandidType, Decode, Encode, Nat};
 
+use anyhow::{anyhow, bail};
 use delay::{Delay, Waiter};
+use futures::{StreamExt, TryStreamExt};
 use ic_agent::Agent;
 use ic_types::Principal;
 use mime::Mime;
+use regex::Regex;
 use serde::Deserialize;
-use std::collections::HashMap;
+use slog::{info, Logger};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
 use std::time::Duration;
 use walkdir::WalkDir;
 
+mod config;
 mod content;
 mod content_encoder;
 
+pub use config::ASSETS_CONFIG_FILE_NAME;
+
 const CONTENT_ENCODING_IDENTITY: &str = "identity";
 const CREATE_BATCH: &str = "create_batch";
 const CREATE_CHUNK: &str = "create_chunk";
 const COMMIT_BATCH: &str = "commit_batch";
 const LIST: &str = "list";
 const MAX_CHUNK_SIZE: usize = 1_900_000;
+/// The maximum number of chunks uploaded at the same time.
+const MAX_CONCURRENT_UPLOADS: usize = 8;
 
 #[derive(CandidType, Debug)]
 struct CreateBatchRequest {}
@@ -63,13 +76,13 @@ struct GetResponse {
 #[derive(CandidType, Debug)]
 struct ListAssetsRequest {}
 
-#[derive(CandidType, Debug, Deserialize)]
+#[derive(CandidType, Clone, Debug, Deserialize)]
 struct AssetEncodingDetails {
     content_encoding: String,
     sha256: Option<Vec<u8>>,
 }
 
-#[derive(CandidType, Debug, Deserialize)]
+#[derive(CandidType, Clone, Debug, Deserialize)]
 struct AssetDetails {
     key: String,
     encodings: Vec<AssetEncodingDetails>,
@@ -123,10 +136,11 @@ struct CommitBatchArguments<'a> {
 struct AssetLocation {
     source: PathBuf,
     key: String,
+    /// The properties set by the `.ic-assets.json` files.
+    properties: AssetProperties,
 }
 
 struct ProjectAssetEncoding {
-    chunk_ids: Vec<Nat>,
     sha256: Vec<u8>,
     already_in_place: bool,
 }
@@ -134,7 +148,7 @@ struct ProjectAssetEncoding {
 struct ProjectAsset {
     asset_location: AssetLocation,
     media_type: Mime,
-    encodings: HashMap<String, ProjectAssetEncoding>,
+    encodings: BTreeMap<String, ProjectAssetEncoding>,
 }
 
 struct CanisterCallParams<'a> {
@@ -175,7 +189,8 @@ async fn create_chunk(
             Ok(chunk_id) => {
                 break Ok(chunk_id);
             }
-            Err(agent_err) => match waiter.wait() {
+            // Wait without blocking the uploads of the other chunks.
+            Err(agent_err) => match waiter.async_wait().await {
                 Ok(()) => {}
                 Err(_) => break Err(agent_err),
             },
@@ -183,47 +198,65 @@ async fn create_chunk(
     }
 }
 
+/// Upload the contents of the pending uploads in chunks, with at most
+/// `MAX_CONCURRENT_UPLOADS` chunks in flight. Returns the chunk IDs of each upload.
 async fn upload_content_chunks(
     canister_call_params: &CanisterCallParams<'_>,
     batch_id: &Nat,
-    asset_location: &AssetLocation,
-    content: &Content,
-    content_encoding: &str,
-) -> DfxResult<Vec<Nat>> {
-    let mut chunk_ids: Vec<Nat> = vec![];
-    let chunks = content.data.chunks(MAX_CHUNK_SIZE);
-    let (num_chunks, _) = chunks.size_hint();
-    for (i, data_chunk) in chunks.enumerate() {
-        println!(
-            "  {}{} {}/{} ({} bytes)",
-            &asset_location.key,
-            content_encoding_descriptive_suffix(content_encoding),
-            i + 1,
-            num_chunks,
-            data_chunk.len(),
-        );
-        chunk_ids.push(create_chunk(canister_call_params, batch_id, data_chunk).await?);
-    }
-    if chunk_ids.is_empty() {
-        println!(
-            "  {}{} 1/1 (0 bytes)",
-            &asset_location.key,
-            content_encoding_descriptive_suffix(content_encoding)
-        );
-        let empty = vec![];
-        chunk_ids.push(create_chunk(canister_call_params, batch_id, &empty).await?);
+    uploads: &[PendingUpload],
+    progress: &ProgressBar,
+) -> DfxResult<Vec<Vec<Nat>>> {
+    // An empty content is uploaded as one empty chunk.
+    let chunks: Vec<(usize, &[u8])> = uploads
+        .iter()
+        .enumerate()
+        .flat_map(|(index, upload)| {
+            let data = upload.content.data.as_slice();
+            if data.is_empty() {
+                vec![(index, data)]
+            } else {
+                data.chunks(MAX_CHUNK_SIZE)
+                    .map(|chunk| (index, chunk))
+                    .collect()
+            }
+        })
+        .collect();
+    progress.set_length(chunks.iter().map(|(_, chunk)| chunk.len() as u64).sum());
+
+    // The results come in the order of the chunks, so the chunk IDs of each upload are
+    // in order too.
+    let chunk_ids: Vec<(usize, Nat)> = futures::stream::iter(chunks)
+        .map(|(index, chunk)| async move {
+            let chunk_id = create_chunk(canister_call_params, batch_id, chunk).await?;
+            progress.inc(chunk.len() as u64);
+            Ok::<_, DfxError>((index, chunk_id))
+        })
+        .buffered(MAX_CONCURRENT_UPLOADS)
+        .try_collect()
+        .await?;
+
+    let mut upload_chunk_ids = vec![vec![]; uploads.len()];
+    for (index, chunk_id) in chunk_ids {
+        upload_chunk_ids[index].push(chunk_id);
     }
-    Ok(chunk_ids)
+    Ok(upload_chunk_ids)
 }
 
-async fn make_project_asset_encoding(
-    canister_call_params: &CanisterCallParams<'_>,
-    batch_id: &Nat,
+/// An encoding of an asset whose content is not in the canister yet.
+struct PendingUpload {
+    key: String,
+    content_encoding: String,
+    content: Content,
+}
+
+fn make_project_asset_encoding(
+    logger: &Logger,
     asset_location: &AssetLocation,
-    container_assets: &HashMap<String, AssetDetails>,
-    content: &Content,
+    container_assets: &BTreeMap<String, AssetDetails>,
+    content: Content,
     content_encoding: &str,
-) -> DfxResult<ProjectAssetEncoding> {
+    uploads: &mut Vec<PendingUpload>,
+) -> ProjectAssetEncoding {
     let sha256 = content.sha256();
 
     let already_in_place = if let Some(container_asset) = container_assets.get(&asset_location.key)
@@ -244,31 +277,27 @@ async fn make_project_asset_encoding(
         false
     };
 
-    let chunk_ids = if already_in_place {
-        println!(
+    if already_in_place {
+        info!(
+            logger,
             "  {}{} ({} bytes) sha {} is already installed",
             &asset_location.key,
             content_encoding_descriptive_suffix(content_encoding),
             content.data.len(),
-            hex::encode(&sha256),
+            hex::encode(&sha256)
         );
-        vec![]
     } else {
-        upload_content_chunks(
-            canister_call_params,
-            batch_id,
-            &asset_location,
+        uploads.push(PendingUpload {
+            key: asset_location.key.clone(),
+            content_encoding: content_encoding.to_string(),
             content,
-            content_encoding,
-        )
-        .await?
-    };
+        });
+    }
 
-    Ok(ProjectAssetEncoding {
-        chunk_ids,
+    ProjectAssetEncoding {
         sha256,
         already_in_place,
-    })
+    }
 }
 
 fn content_encoding_descriptive_suffix(content_encoding: &str) -> String {
@@ -279,111 +308,272 @@ fn content_encoding_descriptive_suffix(content_encoding: &str) -> String {
     }
 }
 
-async fn make_project_asset(
-    canister_call_params: &CanisterCallParams<'_>,
-    batch_id: &Nat,
+fn make_project_asset(
+    logger: &Logger,
     asset_location: AssetLocation,
-    container_assets: &HashMap<String, AssetDetails>,
+    container_assets: &BTreeMap<String, AssetDetails>,
+    encoding_rules: &[EncodingRule],
+    savings: &mut BTreeMap<String, usize>,
+    uploads: &mut Vec<PendingUpload>,
 ) -> DfxResult<ProjectAsset> {
-    let content = Content::load(&asset_location.source)?;
+    let mut content = Content::load(&asset_location.source)?;
+    if let Some(content_type) = &asset_location.properties.content_type {
+        content.media_type = content_type.parse().map_err(|_| {
+            anyhow!(
+                "Invalid content type '{}' for asset {}.",
+                content_type,
+                asset_location.key
+            )
+        })?;
+    }
+    let media_type = content.media_type.clone();
 
+    let encoders = match &asset_location.properties.encodings {
+        Some(encodings) => encodings
+            .iter()
+            .map(|name| {
+                let encoder = ContentEncoder::from_name(name)?;
+                Ok((encoder, encoder.default_level()))
+            })
+            .collect::<DfxResult<Vec<_>>>()?,
+        None => applicable_encoders(encoding_rules, &asset_location.key, &media_type),
+    };
     let encodings = make_encodings(
-        canister_call_params,
-        batch_id,
+        logger,
         &asset_location,
         container_assets,
-        &content,
-    )
-    .await?;
+        content,
+        encoders,
+        savings,
+        uploads,
+    )?;
 
     Ok(ProjectAsset {
         asset_location,
-        media_type: content.media_type,
+        media_type,
         encodings,
     })
 }
 
-// todo: make this configurable https://github.com/dfinity/dx-triage/issues/152
-fn applicable_encoders(media_type: &Mime) -> Vec<ContentEncoder> {
+/// A compression rule of the assets canister, ready to match the keys of assets.
+struct EncodingRule {
+    pattern: Regex,
+    /// Whether the pattern matches the file names of the assets rather than their keys.
+    file_name_only: bool,
+    encoders: Vec<(ContentEncoder, u32)>,
+}
+
+fn make_encoding_rules(rules: &[CompressionRule]) -> DfxResult<Vec<EncodingRule>> {
+    let mut encoding_rules = vec![];
+    for rule in rules {
+        let mut encoders = vec![];
+        for name in &rule.encodings {
+            let encoder = ContentEncoder::from_name(name)?;
+            let level = rule.level.unwrap_or_else(|| encoder.default_level());
+            if level > encoder.max_level() {
+                bail!(
+                    "The compression level of {} for '{}' must be at most {}.",
+                    encoder,
+                    rule.pattern,
+                    encoder.max_level()
+                );
+            }
+            encoders.push((encoder, level));
+        }
+        encoding_rules.push(EncodingRule {
+            pattern: glob_to_regex(rule.pattern.trim_start_matches('/'))?,
+            file_name_only: !rule.pattern.contains('/'),
+            encoders,
+        });
+    }
+    Ok(encoding_rules)
+}
+
+/// Whether the content of a media type is worth compressing by default.
+fn is_compressible(media_type: &Mime) -> bool {
     match (media_type.type_(), media_type.subtype()) {
-        (mime::TEXT, _) | (_, mime::JAVASCRIPT) | (_, mime::HTML) => vec![ContentEncoder::Gzip],
-        _ => vec![],
+        (mime::TEXT, _) | (mime::FONT, _) => true,
+        (_, mime::JAVASCRIPT) | (_, mime::HTML) | (_, mime::JSON) | (_, mime::SVG) => true,
+        (mime::APPLICATION, subtype) => subtype == "wasm" || subtype.as_str().starts_with("font-"),
+        _ => false,
     }
 }
 
-async fn make_encodings(
-    canister_call_params: &CanisterCallParams<'_>,
-    batch_id: &Nat,
+/// The encoders of an asset, with their compression level, from the first compression rule
+/// matching it. Without a matching rule, compressible media types are encoded with gzip and
+/// brotli.
+fn applicable_encoders(
+    encoding_rules: &[EncodingRule],
+    key: &str,
+    media_type: &Mime,
+) -> Vec<(ContentEncoder, u32)> {
+    let path = key.trim_start_matches('/');
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let rule = encoding_rules.iter().find(|rule| {
+        rule.pattern
+            .is_match(if rule.file_name_only { file_name } else { path })
+    });
+    match rule {
+        Some(rule) => rule.encoders.clone(),
+        None if is_compressible(media_type) => [ContentEncoder::Gzip, ContentEncoder::Brotli]
+            .iter()
+            .map(|encoder| (*encoder, encoder.default_level()))
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn make_encodings(
+    logger: &Logger,
     asset_location: &AssetLocation,
-    container_assets: &HashMap<String, AssetDetails>,
-    content: &Content,
-) -> DfxResult<HashMap<String, ProjectAssetEncoding>> {
-    let mut encodings = HashMap::new();
+    container_assets: &BTreeMap<String, AssetDetails>,
+    content: Content,
+    encoders: Vec<(ContentEncoder, u32)>,
+    savings: &mut BTreeMap<String, usize>,
+    uploads: &mut Vec<PendingUpload>,
+) -> DfxResult<BTreeMap<String, ProjectAssetEncoding>> {
+    let mut encodings = BTreeMap::new();
+
+    for (encoder, level) in encoders {
+        let encoded = content.encode(&encoder, level)?;
+        let content_encoding = format!("{}", encoder);
+        if encoded.data.len() >= content.data.len() {
+            info!(
+                logger,
+                "  {} ({}) is not smaller than the original, skipping",
+                &asset_location.key,
+                content_encoding
+            );
+        } else {
+            *savings.entry(content_encoding.clone()).or_default() +=
+                content.data.len() - encoded.data.len();
+            let project_asset_encoding = make_project_asset_encoding(
+                logger,
+                &asset_location,
+                container_assets,
+                encoded,
+                &content_encoding,
+                uploads,
+            );
+            encodings.insert(content_encoding, project_asset_encoding);
+        }
+    }
 
     let identity_asset_encoding = make_project_asset_encoding(
-        canister_call_params,
-        batch_id,
+        logger,
         &asset_location,
         container_assets,
-        &content,
+        content,
         CONTENT_ENCODING_IDENTITY,
-    )
-    .await?;
+        uploads,
+    );
     encodings.insert(
         CONTENT_ENCODING_IDENTITY.to_string(),
         identity_asset_encoding,
     );
 
-    for encoder in applicable_encoders(&content.media_type) {
-        let encoded = content.encode(&encoder)?;
-        if encoded.data.len() < content.data.len() {
-            let content_encoding = format!("{}", encoder);
-            let project_asset_encoding = make_project_asset_encoding(
-                canister_call_params,
-                batch_id,
-                &asset_location,
-                container_assets,
-                &encoded,
-                &content_encoding,
-            )
-            .await?;
-            encodings.insert(content_encoding, project_asset_encoding);
-        }
-    }
-
     Ok(encodings)
 }
 
-async fn make_project_assets(
-    canister_call_params: &CanisterCallParams<'_>,
-    batch_id: &Nat,
+/// Returns the project assets, and the encodings whose content is not in the canister yet.
+fn make_project_assets(
+    logger: &Logger,
     locs: Vec<AssetLocation>,
-    container_assets: &HashMap<String, AssetDetails>,
-) -> DfxResult<HashMap<String, ProjectAsset>> {
-    let mut project_assets = HashMap::new();
+    container_assets: &BTreeMap<String, AssetDetails>,
+    encoding_rules: &[EncodingRule],
+) -> DfxResult<(BTreeMap<String, ProjectAsset>, Vec<PendingUpload>)> {
+    let mut project_assets = BTreeMap::new();
+    let mut savings = BTreeMap::new();
+    let mut uploads = vec![];
     for loc in locs {
-        let project_asset =
-            make_project_asset(canister_call_params, batch_id, loc, &container_assets).await?;
+        let project_asset = make_project_asset(
+            logger,
+            loc,
+            &container_assets,
+            encoding_rules,
+            &mut savings,
+            &mut uploads,
+        )?;
         project_assets.insert(project_asset.asset_location.key.clone(), project_asset);
     }
-    Ok(project_assets)
+    for (content_encoding, saved) in savings {
+        info!(
+            logger,
+            "The {} encodings save {} bytes.", content_encoding, saved
+        );
+    }
+    Ok((project_assets, uploads))
 }
 
-async fn commit_batch(
-    canister_call_params: &CanisterCallParams<'_>,
-    batch_id: &Nat,
-    project_assets: HashMap<String, ProjectAsset>,
-    container_assets: HashMap<String, AssetDetails>,
-) -> DfxResult {
-    let mut container_assets = container_assets;
-
+/// The operations of the batch that makes the assets of the canister match the project.
+/// The chunks of the contents set are filled in once they are uploaded.
+fn batch_operations(
+    project_assets: &BTreeMap<String, ProjectAsset>,
+    container_assets: &BTreeMap<String, AssetDetails>,
+) -> Vec<BatchOperationKind> {
+    let mut container_assets = container_assets.clone();
     let mut operations = vec![];
 
-    delete_obsolete_assets(&mut operations, &project_assets, &mut container_assets);
-    create_new_assets(&mut operations, &project_assets, &container_assets);
-    unset_obsolete_encodings(&mut operations, &project_assets, &container_assets);
+    delete_obsolete_assets(&mut operations, project_assets, &mut container_assets);
+    create_new_assets(&mut operations, project_assets, &container_assets);
+    unset_obsolete_encodings(&mut operations, project_assets, &container_assets);
     set_encodings(&mut operations, project_assets);
+    operations
+}
+
+/// Describe the operations of a batch, as listed by `dfx canister sync-assets --dry-run`.
+fn describe_operations(
+    operations: &[BatchOperationKind],
+    container_assets: &BTreeMap<String, AssetDetails>,
+) -> Vec<String> {
+    let deleted: BTreeSet<&str> = operations
+        .iter()
+        .filter_map(|operation| match operation {
+            BatchOperationKind::DeleteAsset(args) => Some(args.key.as_str()),
+            _ => None,
+        })
+        .collect();
+    operations
+        .iter()
+        .map(|operation| match operation {
+            BatchOperationKind::CreateAsset(args) => {
+                format!("create {} ({})", args.key, args.content_type)
+            }
+            BatchOperationKind::SetAssetContent(args) => {
+                let old_sha256 = container_assets
+                    .get(&args.key)
+                    .filter(|_| !deleted.contains(args.key.as_str()))
+                    .and_then(|details| {
+                        details
+                            .encodings
+                            .iter()
+                            .find(|encoding| encoding.content_encoding == args.content_encoding)
+                    })
+                    .and_then(|encoding| encoding.sha256.as_ref());
+                format!(
+                    "set {}{} (sha256 {} -> {})",
+                    args.key,
+                    content_encoding_descriptive_suffix(&args.content_encoding),
+                    old_sha256.map_or_else(|| "none".to_string(), hex::encode),
+                    args.sha256
+                        .as_ref()
+                        .map_or_else(|| "none".to_string(), hex::encode)
+                )
+            }
+            BatchOperationKind::UnsetAssetContent(args) => {
+                format!("unset {} ({})", args.key, args.content_encoding)
+            }
+            BatchOperationKind::DeleteAsset(args) => format!("delete {}", args.key),
+            BatchOperationKind::_Clear(_) => "clear all assets".to_string(),
+        })
+        .collect()
+}
 
+async fn commit_batch(
+    canister_call_params: &CanisterCallParams<'_>,
+    batch_id: &Nat,
+    operations: Vec<BatchOperationKind>,
+) -> DfxResult {
     let arg = CommitBatchArguments {
         batch_id,
         operations,
@@ -401,8 +591,8 @@ async fn commit_batch(
 
 fn delete_obsolete_assets(
     operations: &mut Vec<BatchOperationKind>,
-    project_assets: &HashMap<String, ProjectAsset>,
-    container_assets: &mut HashMap<String, AssetDetails>,
+    project_assets: &BTreeMap<String, ProjectAsset>,
+    container_assets: &mut BTreeMap<String, AssetDetails>,
 ) {
     let mut deleted_container_assets = vec![];
     for (key, container_asset) in container_assets.iter() {
@@ -424,8 +614,8 @@ fn delete_obsolete_assets(
 
 fn create_new_assets(
     operations: &mut Vec<BatchOperationKind>,
-    project_assets: &HashMap<String, ProjectAsset>,
-    container_assets: &HashMap<String, AssetDetails>,
+    project_assets: &BTreeMap<String, ProjectAsset>,
+    container_assets: &BTreeMap<String, AssetDetails>,
 ) {
     for (key, project_asset) in project_assets {
         if !container_assets.contains_key(key) {
@@ -439,8 +629,8 @@ fn create_new_assets(
 
 fn unset_obsolete_encodings(
     operations: &mut Vec<BatchOperationKind>,
-    project_assets: &HashMap<String, ProjectAsset>,
-    container_assets: &HashMap<String, AssetDetails>,
+    project_assets: &BTreeMap<String, ProjectAsset>,
+    container_assets: &BTreeMap<String, AssetDetails>,
 ) {
     for (key, details) in container_assets {
         let project_asset = project_assets.get(key);
@@ -466,10 +656,10 @@ fn unset_obsolete_encodings(
 
 fn set_encodings(
     operations: &mut Vec<BatchOperationKind>,
-    project_assets: HashMap<String, ProjectAsset>,
+    project_assets: &BTreeMap<String, ProjectAsset>,
 ) {
     for (key, project_asset) in project_assets {
-        for (content_encoding, v) in project_asset.encodings {
+        for (content_encoding, v) in &project_asset.encodings {
             if v.already_in_place {
                 continue;
             }
@@ -477,9 +667,9 @@ fn set_encodings(
             operations.push(BatchOperationKind::SetAssetContent(
                 SetAssetContentArguments {
                     key: key.clone(),
-                    content_encoding,
-                    chunk_ids: v.chunk_ids,
-                    sha256: Some(v.sha256),
+                    content_encoding: content_encoding.clone(),
+                    chunk_ids: vec![],
+                    sha256: Some(v.sha256.clone()),
                 },
             ));
         }
@@ -487,26 +677,51 @@ fn set_encodings(
 }
 
 pub async fn post_install_store_assets(
+    env: &dyn Environment,
+    info: &CanisterInfo,
+    agent: &Agent,
+    timeout: Duration,
+) -> DfxResult {
+    sync_assets(env, info, agent, timeout, false).await
+}
+
+/// Make the assets of an assets canister match its built assets. With `dry_run`, only list
+/// the operations of the batch and the bytes to upload.
+pub async fn sync_assets(
+    env: &dyn Environment,
     info: &CanisterInfo,
     agent: &Agent,
     timeout: Duration,
+    dry_run: bool,
 ) -> DfxResult {
+    let logger = env.get_logger();
     let assets_canister_info = info.as_info::<AssetsCanisterInfo>()?;
     let output_assets_path = assets_canister_info.get_output_assets_path();
+    let encoding_rules = make_encoding_rules(assets_canister_info.get_compression_rules())?;
+    let assets_config = AssetsConfig::load(output_assets_path)?;
 
     let asset_locations: Vec<AssetLocation> = WalkDir::new(output_assets_path)
         .into_iter()
         .filter_map(|r| {
-            r.ok().filter(|entry| entry.file_type().is_file()).map(|e| {
-                let source = e.path().to_path_buf();
-                let relative = source
-                    .strip_prefix(output_assets_path)
-                    .expect("cannot strip prefix");
-                let key = String::from("/") + relative.to_string_lossy().as_ref();
-
-                AssetLocation { source, key }
-            })
+            r.ok()
+                .filter(|entry| entry.file_type().is_file())
+                .filter(|entry| entry.file_name() != ASSETS_CONFIG_FILE_NAME)
+                .map(|e| {
+                    let source = e.path().to_path_buf();
+                    let relative = source
+                        .strip_prefix(output_assets_path)
+                        .expect("cannot strip prefix");
+                    let key = String::from("/") + relative.to_string_lossy().as_ref();
+                    let properties = assets_config.get_properties(&key);
+
+                    AssetLocation {
+                        source,
+                        key,
+                        properties,
+                    }
+                })
         })
+        .filter(|location| !location.properties.ignore)
         .collect();
 
     let canister_id = info.get_canister_id().expect("Could not find canister ID.");
@@ -517,24 +732,44 @@ pub async fn post_install_store_assets(
     };
 
     let container_assets = list_assets(&canister_call_params).await?;
+    let (project_assets, uploads) =
+        make_project_assets(logger, asset_locations, &container_assets, &encoding_rules)?;
+    let mut operations = batch_operations(&project_assets, &container_assets);
+
+    if dry_run {
+        if operations.is_empty() {
+            println!("The assets are up to date.");
+        }
+        for description in describe_operations(&operations, &container_assets) {
+            println!("  {}", description);
+        }
+        let bytes: usize = uploads.iter().map(|upload| upload.content.data.len()).sum();
+        println!("{} bytes to upload.", bytes);
+        return Ok(());
+    }
 
     let batch_id = create_batch(&canister_call_params).await?;
 
-    let project_assets = make_project_assets(
-        &canister_call_params,
-        &batch_id,
-        asset_locations,
-        &container_assets,
-    )
-    .await?;
-
-    commit_batch(
-        &canister_call_params,
-        &batch_id,
-        project_assets,
-        container_assets,
-    )
-    .await?;
+    let progress = env.new_progress("Uploading assets");
+    let chunk_ids =
+        upload_content_chunks(&canister_call_params, &batch_id, &uploads, &progress).await?;
+    progress.finish_with_message("Uploaded assets");
+    let mut chunk_ids: BTreeMap<(&str, &str), Vec<Nat>> = uploads
+        .iter()
+        .map(|upload| (upload.key.as_str(), upload.content_encoding.as_str()))
+        .zip(chunk_ids)
+        .collect();
+    for operation in &mut operations {
+        if let BatchOperationKind::SetAssetContent(args) = operation {
+            if let Some(ids) =
+                chunk_ids.remove(&(args.key.as_str(), args.content_encoding.as_str()))
+            {
+                args.chunk_ids = ids;
+            }
+        }
+    }
+
+    commit_batch(&canister_call_params, &batch_id, operations).await?;
 
     Ok(())
 }
@@ -554,7 +789,7 @@ async fn create_batch(canister_call_params: &CanisterCallParams<'_>) -> DfxResul
 
 async fn list_assets(
     canister_call_params: &CanisterCallParams<'_>,
-) -> DfxResult<HashMap<String, AssetDetails>> {
+) -> DfxResult<BTreeMap<String, AssetDetails>> {
     let args = ListAssetsRequest {};
     let response = canister_call_params
         .agent
@@ -564,10 +799,165 @@ async fn list_assets(
         .call_and_wait(waiter_with_timeout(canister_call_params.timeout))
         .await?;
 
-    let assets: HashMap<_, _> = candid::Decode!(&response, Vec<AssetDetails>)?
+    let assets: BTreeMap<_, _> = candid::Decode!(&response, Vec<AssetDetails>)?
         .into_iter()
         .map(|d| (d.key.clone(), d))
         .collect();
 
     Ok(assets)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encodings(rules: &[EncodingRule], key: &str, media_type: Mime) -> Vec<(String, u32)> {
+        applicable_encoders(rules, key, &media_type)
+            .iter()
+            .map(|(encoder, level)| (encoder.to_string(), *level))
+            .collect()
+    }
+
+    #[test]
+    fn first_matching_rule_chooses_encoders() {
+        let rules = make_encoding_rules(&[
+            CompressionRule {
+                pattern: "*.wasm".to_string(),
+                encodings: vec!["br".to_string()],
+                level: Some(9),
+            },
+            CompressionRule {
+                pattern: "/fonts/**".to_string(),
+                encodings: vec![],
+                level: None,
+            },
+        ])
+        .unwrap();
+
+        let wasm: Mime = "application/wasm".parse().unwrap();
+        let font: Mime = "font/ttf".parse().unwrap();
+        assert_eq!(
+            encodings(&rules, "/app/main.wasm", wasm.clone()),
+            vec![("br".to_string(), 9)]
+        );
+        assert_eq!(encodings(&rules, "/fonts/a.ttf", font.clone()), vec![]);
+        assert_eq!(
+            encodings(&rules, "/other/a.ttf", font),
+            vec![("gzip".to_string(), 6), ("br".to_string(), 11)]
+        );
+        assert_eq!(encodings(&rules, "/logo.png", mime::IMAGE_PNG), vec![]);
+        assert_eq!(
+            encodings(&[], "/app/main.wasm", wasm),
+            vec![("gzip".to_string(), 6), ("br".to_string(), 11)]
+        );
+    }
+
+    #[test]
+    fn sniffed_media_types_choose_encoders() {
+        let dir = tempfile::tempdir().unwrap();
+        let redirects = dir.path().join("_redirects");
+        std::fs::write(&redirects, "/*  /index.html  200\n").unwrap();
+        let bundle = dir.path().join("logo-3f2a9c");
+        std::fs::write(&bundle, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        let redirects = Content::load(&redirects).unwrap();
+        assert_eq!(redirects.media_type, mime::TEXT_PLAIN);
+        assert_eq!(
+            encodings(&[], "/_redirects", redirects.media_type),
+            vec![("gzip".to_string(), 6), ("br".to_string(), 11)]
+        );
+        let bundle = Content::load(&bundle).unwrap();
+        assert_eq!(bundle.media_type, mime::IMAGE_PNG);
+        assert_eq!(encodings(&[], "/logo-3f2a9c", bundle.media_type), vec![]);
+    }
+
+    #[test]
+    fn describes_the_operations_of_the_batch() {
+        let project_asset = |key: &str, media_type: Mime, encodings: &[(&str, u8, bool)]| {
+            let asset = ProjectAsset {
+                asset_location: AssetLocation {
+                    source: PathBuf::from(key),
+                    key: key.to_string(),
+                    properties: AssetProperties::default(),
+                },
+                media_type,
+                encodings: encodings
+                    .iter()
+                    .map(|(content_encoding, sha256, already_in_place)| {
+                        let encoding = ProjectAssetEncoding {
+                            sha256: vec![*sha256],
+                            already_in_place: *already_in_place,
+                        };
+                        (content_encoding.to_string(), encoding)
+                    })
+                    .collect(),
+            };
+            (key.to_string(), asset)
+        };
+        let container_asset = |key: &str, content_type: &str, encodings: &[(&str, u8)]| {
+            let details = AssetDetails {
+                key: key.to_string(),
+                encodings: encodings
+                    .iter()
+                    .map(|(content_encoding, sha256)| AssetEncodingDetails {
+                        content_encoding: content_encoding.to_string(),
+                        sha256: Some(vec![*sha256]),
+                    })
+                    .collect(),
+                content_type: content_type.to_string(),
+            };
+            (key.to_string(), details)
+        };
+
+        let project_assets: BTreeMap<_, _> = vec![
+            project_asset("/new.html", mime::TEXT_HTML, &[("identity", 1, false)]),
+            project_asset("/app.js", mime::TEXT_JAVASCRIPT, &[("identity", 2, false)]),
+            project_asset(
+                "/style.css",
+                mime::TEXT_CSS,
+                &[("identity", 3, true), ("br", 4, false)],
+            ),
+            project_asset("/same.txt", mime::TEXT_PLAIN, &[("identity", 5, true)]),
+            project_asset("/data.bin", mime::TEXT_PLAIN, &[("identity", 9, false)]),
+        ]
+        .into_iter()
+        .collect();
+        let container_assets: BTreeMap<_, _> = vec![
+            container_asset("/old.html", "text/html", &[("identity", 6)]),
+            container_asset("/app.js", "text/javascript", &[("identity", 7)]),
+            container_asset("/style.css", "text/css", &[("identity", 3), ("gzip", 8)]),
+            container_asset("/same.txt", "text/plain", &[("identity", 5)]),
+            container_asset("/data.bin", "application/octet-stream", &[("identity", 9)]),
+        ]
+        .into_iter()
+        .collect();
+
+        let operations = batch_operations(&project_assets, &container_assets);
+        assert_eq!(
+            describe_operations(&operations, &container_assets),
+            vec![
+                "delete /data.bin",
+                "delete /old.html",
+                "create /data.bin (text/plain)",
+                "create /new.html (text/html)",
+                "unset /style.css (gzip)",
+                "set /app.js (sha256 07 -> 02)",
+                "set /data.bin (sha256 none -> 09)",
+                "set /new.html (sha256 none -> 01)",
+                "set /style.css (br) (sha256 none -> 04)",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_encodings_and_levels() {
+        let rule = |encoding: &str, level| CompressionRule {
+            pattern: "*".to_string(),
+            encodings: vec![encoding.to_string()],
+            level: Some(level),
+        };
+        assert!(make_encoding_rules(&[rule("br", 11)]).is_ok());
+        assert!(make_encoding_rules(&[rule("br", 12)]).is_err());
+        assert!(make_encoding_rules(&[rule("deflate", 1)]).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+//! The metadata of canisters, which the build stores in custom sections of their Wasm
+//! modules.
+use crate::config::dfx_version_str;
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::error::DfxResult;
+
+use std::path::Path;
+use std::process::Command;
+
+/// The Candid interface of the canister.
+pub const CANDID_SERVICE: &str = "candid:service";
+/// The version of dfx that built the canister.
+pub const DFX_VERSION: &str = "dfx:version";
+/// The type of the builder of the canister, e.g. `motoko`.
+pub const DFX_BUILDER: &str = "dfx:builder";
+/// The git revision of the project, when it is in a git repository.
+pub const GIT_REVISION: &str = "git:revision";
+
+/// The name of the custom section of a public metadata.
+pub fn public_section_name(name: &str) -> String {
+    format!("icp:public {}", name)
+}
+
+/// The metadata embedded in the Wasm module of a canister built by dfx.
+pub fn build_metadata(
+    info: &CanisterInfo,
+    git_revision: Option<&str>,
+) -> DfxResult<Vec<(&'static str, Vec<u8>)>> {
+    let mut metadata = vec![
+        (CANDID_SERVICE, std::fs::read(info.get_build_idl_path())?),
+        (DFX_VERSION, dfx_version_str().as_bytes().to_vec()),
+        (DFX_BUILDER, info.get_type().as_bytes().to_vec()),
+    ];
+
+    if let Some(revision) = git_revision {
+        metadata.push((GIT_REVISION, revision.as_bytes().to_vec()));
+    }
+    Ok(metadata)
+}
+
+/// The git revision of the project, or None if it is not in a git repository.
+pub fn git_revision(workspace_root: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(&["rev-parse", "HEAD"])
+        .current_dir(workspace_root)
+        .output()
+        .ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
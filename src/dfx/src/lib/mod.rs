@@ -1,6 +1,9 @@
 pub mod builders;
+pub mod candid_compat;
 pub mod canister_info;
 pub mod config;
+pub mod declarations;
+pub mod diagnostics;
 pub mod dist;
 pub mod environment;
 pub mod error;
@@ -10,6 +13,7 @@ pub mod installers;
 pub mod locations;
 pub mod logger;
 pub mod manifest;
+pub mod metadata;
 pub mod models;
 pub mod named_canister;
 pub mod network;
@@ -24,4 +28,6 @@ pub mod sign;
 pub mod telemetry;
 pub mod toolchain;
 pub mod waiter;
+pub mod wasm;
+pub mod watch;
 pub mod webserver;
@@ -1,2 +1,4 @@
+pub mod build_cache;
+pub mod build_manifest;
 pub mod canister;
 pub mod canister_id_store;
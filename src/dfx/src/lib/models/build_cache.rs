@@ -0,0 +1,156 @@
+use crate::lib::error::DfxResult;
+
+use anyhow::Context;
+use openssl::sha::Sha256;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub const BUILD_CACHE_FILE_NAME: &str = "build-cache.json";
+
+type CanisterName = String;
+type FingerprintString = String;
+
+/// The fingerprints of the inputs of the last successful build of each canister, stored
+/// in the build root (e.g. `.dfx/local/canisters/build-cache.json`).
+#[derive(Clone, Debug)]
+pub struct BuildCache {
+    path: PathBuf,
+    fingerprints: BTreeMap<CanisterName, FingerprintString>,
+}
+
+impl BuildCache {
+    /// Load the build cache of a build root. A missing or unreadable cache is empty, which
+    /// only means that every canister is built.
+    pub fn load(build_root: &Path) -> Self {
+        let path = build_root.join(BUILD_CACHE_FILE_NAME);
+        let fingerprints = std::fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+        BuildCache { path, fingerprints }
+    }
+
+    /// Returns true if the canister was last built from inputs with this fingerprint.
+    pub fn is_fresh(&self, canister_name: &str, fingerprint: &str) -> bool {
+        self.fingerprints.get(canister_name).map(String::as_str) == Some(fingerprint)
+    }
+
+    pub fn insert(&mut self, canister_name: &str, fingerprint: String) {
+        self.fingerprints
+            .insert(canister_name.to_string(), fingerprint);
+    }
+
+    pub fn remove(&mut self, canister_name: &str) {
+        self.fingerprints.remove(canister_name);
+    }
+
+    pub fn save(&self) -> DfxResult {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(&self.fingerprints)?;
+        std::fs::write(&self.path, content)
+            .with_context(|| format!("Cannot write to file at '{}'.", self.path.display()))
+    }
+}
+
+/// A hash of everything that determines the output of a build.
+pub struct Fingerprint {
+    hasher: Sha256,
+}
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Fingerprint::new()
+    }
+}
+
+impl Fingerprint {
+    pub fn new() -> Self {
+        Fingerprint {
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Add a named value. Names keep values of different kinds from colliding.
+    pub fn add_value(&mut self, name: &str, value: &str) {
+        self.add_bytes(name.as_bytes());
+        self.add_bytes(value.as_bytes());
+    }
+
+    /// Add the content of a file, or of every file in a directory. A missing path is added
+    /// as such, so that creating it changes the fingerprint.
+    pub fn add_path(&mut self, path: &Path) -> DfxResult {
+        self.add_value("path", &path.to_string_lossy());
+        if path.is_dir() {
+            let entries = WalkDir::new(path).sort_by(|a, b| a.file_name().cmp(b.file_name()));
+            for entry in entries {
+                let entry = entry?;
+                if entry.file_type().is_file() {
+                    self.add_value("file", &entry.path().to_string_lossy());
+                    self.add_bytes(&std::fs::read(entry.path())?);
+                }
+            }
+        } else if path.is_file() {
+            self.add_bytes(&std::fs::read(path)?);
+        } else {
+            self.add_value("missing", "");
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> String {
+        hex::encode(self.hasher.finish())
+    }
+
+    fn add_bytes(&mut self, bytes: &[u8]) {
+        // Prefix with the length so that consecutive values cannot be confused.
+        self.hasher.update(&(bytes.len() as u64).to_le_bytes());
+        self.hasher.update(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_changes_with_file_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("src").join("main.mo");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+
+        let fingerprint = |dir: &Path| {
+            let mut fingerprint = Fingerprint::new();
+            fingerprint.add_value("type", "motoko");
+            fingerprint.add_path(dir).unwrap();
+            fingerprint.finish()
+        };
+
+        let empty = fingerprint(dir.path());
+        std::fs::write(&file, "actor {}").unwrap();
+        let first = fingerprint(dir.path());
+        assert_ne!(empty, first);
+        assert_eq!(first, fingerprint(dir.path()));
+
+        std::fs::write(&file, "actor { }").unwrap();
+        assert_ne!(first, fingerprint(dir.path()));
+    }
+
+    #[test]
+    fn build_cache_is_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = BuildCache::load(dir.path());
+        assert!(!cache.is_fresh("hello", "abc"));
+
+        cache.insert("hello", "abc".to_string());
+        cache.save().unwrap();
+
+        let mut cache = BuildCache::load(dir.path());
+        assert!(cache.is_fresh("hello", "abc"));
+        assert!(!cache.is_fresh("hello", "def"));
+        cache.remove("hello");
+        assert!(!cache.is_fresh("hello", "abc"));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::config::dfx_version_str;
+use crate::lib::canister_info::CanisterInfo;
+use crate::lib::error::DfxResult;
+
+use anyhow::Context;
+use openssl::sha::sha256;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+pub const BUILD_MANIFEST_FILE_NAME: &str = "build-manifest.json";
+
+/// What the last build produced for each canister, stored in the build root
+/// (e.g. `.dfx/local/canisters/build-manifest.json`) for release notes and audits.
+#[derive(Default, Deserialize, Serialize)]
+pub struct BuildManifest {
+    canisters: Vec<CanisterManifest>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CanisterManifest {
+    name: String,
+    #[serde(rename = "type")]
+    canister_type: String,
+    canister_id: String,
+    wasm: PathBuf,
+    /// The SHA-256 of the Wasm module, which is the module hash of the canister once it
+    /// is installed.
+    wasm_sha256: String,
+    wasm_size: u64,
+    candid: PathBuf,
+    candid_sha256: String,
+    profile: String,
+    dfx_version: String,
+}
+
+/// Returns the path relative to the project, if it is in the project.
+fn project_path(info: &CanisterInfo, path: &Path) -> PathBuf {
+    path.strip_prefix(info.get_workspace_root())
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+impl BuildManifest {
+    /// Load the manifest of the last build in a build root.
+    pub fn load(build_root: &Path) -> DfxResult<Self> {
+        let path = build_root.join(BUILD_MANIFEST_FILE_NAME);
+        let content = std::fs::read(&path).with_context(|| {
+            format!(
+                "Cannot read the build manifest at '{}'. Did you forget to run `dfx build`?",
+                path.display()
+            )
+        })?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// Load the manifest of the last build in a build root, or an empty one if there was
+    /// no build yet.
+    pub fn load_or_default(build_root: &Path) -> DfxResult<Self> {
+        if build_root.join(BUILD_MANIFEST_FILE_NAME).exists() {
+            Self::load(build_root)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Returns the SHA-256 of the Wasm module of a canister, in hex, if it was built.
+    pub fn get_wasm_sha256(&self, canister_name: &str) -> Option<&str> {
+        self.canisters
+            .iter()
+            .find(|c| c.name == canister_name)
+            .map(|c| c.wasm_sha256.as_str())
+    }
+
+    /// Remove the canisters that are no longer in the project.
+    pub fn retain(&mut self, canister_names: &BTreeSet<String>) {
+        self.canisters.retain(|c| canister_names.contains(&c.name));
+    }
+
+    /// Add the output of the build of a canister, replacing that of its previous build.
+    pub fn add(&mut self, info: &CanisterInfo, profile: &str) -> DfxResult {
+        let wasm_path = info.get_build_wasm_path();
+        let wasm = std::fs::read(&wasm_path)
+            .with_context(|| format!("Cannot read file at '{}'.", wasm_path.display()))?;
+        let candid_path = info.get_build_idl_path();
+        let candid = std::fs::read(&candid_path)
+            .with_context(|| format!("Cannot read file at '{}'.", candid_path.display()))?;
+
+        self.insert(CanisterManifest {
+            name: info.get_name().to_string(),
+            canister_type: info.get_type().to_string(),
+            canister_id: info.get_canister_id()?.to_text(),
+            wasm: project_path(info, &wasm_path),
+            wasm_sha256: hex::encode(sha256(&wasm)),
+            wasm_size: wasm.len() as u64,
+            candid: project_path(info, &candid_path),
+            candid_sha256: hex::encode(sha256(&candid)),
+            profile: profile.to_string(),
+            dfx_version: dfx_version_str().to_string(),
+        });
+        Ok(())
+    }
+
+    fn insert(&mut self, canister: CanisterManifest) {
+        match self.canisters.iter_mut().find(|c| c.name == canister.name) {
+            Some(existing) => *existing = canister,
+            None => self.canisters.push(canister),
+        }
+    }
+
+    pub fn save(&self, build_root: &Path) -> DfxResult {
+        std::fs::create_dir_all(build_root)?;
+        let path = build_root.join(BUILD_MANIFEST_FILE_NAME);
+        let content = serde_json::to_string_pretty(&self)?;
+        std::fs::write(&path, content)
+            .with_context(|| format!("Cannot write to file at '{}'.", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canister_manifest(name: &str, wasm_sha256: &str) -> CanisterManifest {
+        CanisterManifest {
+            name: name.to_string(),
+            canister_type: "motoko".to_string(),
+            canister_id: "rrkah-fqaaa-aaaaa-aaaaq-cai".to_string(),
+            wasm: PathBuf::from(format!(".dfx/local/canisters/{0}/{0}.wasm", name)),
+            wasm_sha256: wasm_sha256.to_string(),
+            wasm_size: 0,
+            candid: PathBuf::from(format!(".dfx/local/canisters/{0}/{0}.did", name)),
+            candid_sha256: String::new(),
+            profile: "Debug".to_string(),
+            dfx_version: dfx_version_str().to_string(),
+        }
+    }
+
+    #[test]
+    fn canisters_built_again_replace_their_last_build() {
+        let mut manifest = BuildManifest::default();
+        manifest.insert(canister_manifest("backend", "1"));
+        manifest.insert(canister_manifest("frontend", "2"));
+        manifest.insert(canister_manifest("removed", "3"));
+
+        manifest.retain(
+            &["backend", "frontend"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        );
+        manifest.insert(canister_manifest("backend", "4"));
+
+        assert_eq!(manifest.canisters.len(), 2);
+        assert_eq!(manifest.get_wasm_sha256("backend"), Some("4"));
+        assert_eq!(manifest.get_wasm_sha256("frontend"), Some("2"));
+        assert_eq!(manifest.get_wasm_sha256("removed"), None);
+    }
+}
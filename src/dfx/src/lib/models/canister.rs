@@ -1,24 +1,31 @@
 use crate::config::dfinity::Config;
+use crate::config::dfx_version_str;
 use crate::lib::builders::{
     BuildConfig, BuildOutput, BuilderPool, CanisterBuilder, IdlBuildOutput, WasmBuildOutput,
 };
 use crate::lib::canister_info::CanisterInfo;
+use crate::lib::diagnostics::{emit, emit_diagnostics, tool_stderr, BuildEvent};
 use crate::lib::environment::Environment;
 use crate::lib::error::{BuildError, DfxError, DfxResult};
+use crate::lib::metadata::{build_metadata, public_section_name};
+use crate::lib::models::build_cache::{BuildCache, Fingerprint};
+use crate::lib::models::build_manifest::BuildManifest;
 use crate::lib::models::canister_id_store::CanisterIdStore;
+use crate::lib::wasm;
 use crate::util::{assets, check_candid_file};
 
 use anyhow::anyhow;
 use ic_types::principal::Principal as CanisterId;
 use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction::{Incoming, Outgoing};
 use rand::{thread_rng, RngCore};
-use slog::Logger;
-use std::cell::RefCell;
-use std::collections::BTreeMap;
+use slog::{info, Logger};
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 use std::io::Read;
-use std::path::Path;
-use std::sync::Arc;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 /// Represents a canister from a DFX project. It can be a virtual Canister.
 /// Multiple canister instances can have the same info, but would be differentiated
@@ -28,7 +35,7 @@ use std::sync::Arc;
 pub struct Canister {
     info: CanisterInfo,
     builder: Arc<dyn CanisterBuilder>,
-    output: RefCell<Option<BuildOutput>>,
+    output: Mutex<Option<Arc<BuildOutput>>>,
 }
 
 impl Canister {
@@ -38,7 +45,7 @@ impl Canister {
         Self {
             info,
             builder,
-            output: RefCell::new(None),
+            output: Mutex::new(None),
         }
     }
 
@@ -50,12 +57,12 @@ impl Canister {
         &self,
         pool: &CanisterPool,
         build_config: &BuildConfig,
-    ) -> DfxResult<&BuildOutput> {
-        let output = self.builder.build(pool, &self.info, build_config)?;
+    ) -> DfxResult<Arc<BuildOutput>> {
+        let output = Arc::new(self.builder.build(pool, &self.info, build_config)?);
 
         // Ignore the old output, and return a reference.
-        let _ = self.output.replace(Some(output));
-        Ok(self.get_build_output().unwrap())
+        *self.output.lock().unwrap() = Some(output.clone());
+        Ok(output)
     }
 
     pub fn postbuild(&self, pool: &CanisterPool, build_config: &BuildConfig) -> DfxResult {
@@ -78,10 +85,21 @@ impl Canister {
         Ok(CanisterId::try_from(v)?)
     }
 
+    /// Use the output of the last build of this canister, which is known to be up to date.
+    fn reuse_build_output(&self) -> Arc<BuildOutput> {
+        let output = Arc::new(BuildOutput {
+            canister_id: self.canister_id(),
+            wasm: WasmBuildOutput::File(self.info.get_build_wasm_path()),
+            idl: IdlBuildOutput::File(self.info.get_build_idl_path()),
+        });
+        *self.output.lock().unwrap() = Some(output.clone());
+        output
+    }
+
     /// Get the build output of a build process. If the output isn't known at this time,
     /// will return [None].
-    pub fn get_build_output(&self) -> Option<&BuildOutput> {
-        unsafe { (&*self.output.as_ptr()).as_ref() }
+    pub fn get_build_output(&self) -> Option<Arc<BuildOutput>> {
+        self.output.lock().unwrap().clone()
     }
 }
 
@@ -96,6 +114,7 @@ struct PoolConstructHelper<'a> {
     builder_pool: BuilderPool,
     canister_id_store: CanisterIdStore,
     generate_cid: bool,
+    build_root: Option<&'a Path>,
     canisters_map: &'a mut Vec<Arc<Canister>>,
 }
 
@@ -106,7 +125,10 @@ impl CanisterPool {
             None if pool_helper.generate_cid => Some(Canister::generate_random_canister_id()?),
             _ => None,
         };
-        let info = CanisterInfo::load(pool_helper.config, canister_name, canister_id)?;
+        let mut info = CanisterInfo::load(pool_helper.config, canister_name, canister_id)?;
+        if let Some(build_root) = pool_helper.build_root {
+            info = info.with_build_root(build_root);
+        }
 
         if let Some(builder) = pool_helper.builder_pool.get(&info) {
             pool_helper
@@ -115,8 +137,9 @@ impl CanisterPool {
             Ok(())
         } else {
             Err(anyhow!(
-                "Cannot find builder for canister '{}'.",
-                info.get_name().to_string()
+                "Cannot find builder for canister '{}' of type '{}'. Canisters of other types are built by a 'dfx-builder-<type>' executable in the PATH.",
+                info.get_name().to_string(),
+                info.get_type()
             ))
         }
     }
@@ -125,6 +148,16 @@ impl CanisterPool {
         env: &dyn Environment,
         generate_cid: bool,
         canister_names: &[String],
+    ) -> DfxResult<Self> {
+        CanisterPool::load_with_build_root(env, generate_cid, canister_names, None)
+    }
+
+    /// Load the canisters with their build outputs in another build root, if any.
+    pub fn load_with_build_root(
+        env: &dyn Environment,
+        generate_cid: bool,
+        canister_names: &[String],
+        build_root: Option<&Path>,
     ) -> DfxResult<Self> {
         let logger = env.get_logger().new(slog::o!());
         let config = env
@@ -138,6 +171,7 @@ impl CanisterPool {
             builder_pool: BuilderPool::new(env)?,
             canister_id_store: CanisterIdStore::for_env(env)?,
             generate_cid,
+            build_root,
             canisters_map: &mut canisters_map,
         };
 
@@ -182,7 +216,23 @@ impl CanisterPool {
         &self.logger
     }
 
-    fn build_dependencies_graph(&self) -> DfxResult<DiGraph<CanisterId, ()>> {
+    /// Make the builders find the dependencies and inputs of the canisters again, rather than
+    /// reuse what they found for the previous build.
+    pub fn clear_builder_caches(&self) {
+        for canister in &self.canisters {
+            canister.builder.clear_cache();
+        }
+    }
+
+    /// Returns the files and directories the build of a canister depends on, or None if its
+    /// builder cannot tell.
+    pub fn get_canister_inputs(&self, canister: &Canister) -> DfxResult<Option<Vec<PathBuf>>> {
+        canister.builder.get_inputs(self, &canister.info)
+    }
+
+    /// Returns the graph of the dependencies of the canisters, with edges from a canister to
+    /// its dependencies, and its nodes in topological order (dependents first).
+    fn build_dependencies_graph(&self) -> DfxResult<(DiGraph<CanisterId, ()>, Vec<NodeIndex>)> {
         let mut graph: DiGraph<CanisterId, ()> = DiGraph::new();
         let mut id_set: BTreeMap<CanisterId, NodeIndex<u32>> = BTreeMap::new();
 
@@ -207,20 +257,21 @@ impl CanisterPool {
         }
 
         // Verify the graph has no cycles.
-        if let Err(err) = petgraph::algo::toposort(&graph, None) {
-            let message = match graph.node_weight(err.node_id()) {
-                Some(canister_id) => match self.get_canister_info(canister_id) {
-                    Some(info) => info.get_name().to_string(),
-                    None => format!("<{}>", canister_id.to_text()),
-                },
-                None => "<Unknown>".to_string(),
-            };
-            Err(DfxError::new(BuildError::DependencyError(format!(
-                "Found circular dependency: {}",
-                message
-            ))))
-        } else {
-            Ok(graph)
+        match petgraph::algo::toposort(&graph, None) {
+            Ok(nodes) => Ok((graph, nodes)),
+            Err(err) => {
+                let message = match graph.node_weight(err.node_id()) {
+                    Some(canister_id) => match self.get_canister_info(canister_id) {
+                        Some(info) => info.get_name().to_string(),
+                        None => format!("<{}>", canister_id.to_text()),
+                    },
+                    None => "<Unknown>".to_string(),
+                };
+                Err(DfxError::new(BuildError::DependencyError(format!(
+                    "Found circular dependency: {}",
+                    message
+                ))))
+            }
         }
     }
 
@@ -232,19 +283,84 @@ impl CanisterPool {
         canister.prebuild(self, build_config)
     }
 
-    fn step_build<'a>(
+    fn step_build(
         &self,
         build_config: &BuildConfig,
-        canister: &'a Canister,
-    ) -> DfxResult<&'a BuildOutput> {
+        canister: &Canister,
+    ) -> DfxResult<Arc<BuildOutput>> {
         canister.build(self, build_config)
     }
 
+    /// Returns the fingerprint of everything the build of a canister depends on, or None
+    /// if its builder cannot tell the inputs of the build.
+    fn fingerprint(
+        &self,
+        build_config: &BuildConfig,
+        canister: &Canister,
+    ) -> DfxResult<Option<String>> {
+        let info = &canister.info;
+        let inputs = match canister.builder.get_inputs(self, info)? {
+            Some(inputs) => inputs,
+            None => return Ok(None),
+        };
+
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add_value("dfx", dfx_version_str());
+        fingerprint.add_value("type", info.get_type());
+        fingerprint.add_value("config", &serde_json::to_string(info.get_extras())?);
+        fingerprint.add_value("profile", &build_config.get_profile_fingerprint());
+        fingerprint.add_value("canister_id", &canister.canister_id().to_text());
+        // The revision is embedded in the module as metadata.
+        if let Some(revision) = &build_config.git_revision {
+            fingerprint.add_value("git_revision", revision);
+        }
+        for dependency_id in canister.builder.get_dependencies(self, info)? {
+            fingerprint.add_value("dependency", &dependency_id.to_text());
+            if let Some(dependency) = self.get_canister_info(&dependency_id) {
+                fingerprint.add_path(&dependency.get_build_wasm_path())?;
+                fingerprint.add_path(&dependency.get_build_idl_path())?;
+            }
+        }
+        for input in inputs {
+            fingerprint.add_path(&input)?;
+        }
+        Ok(Some(fingerprint.finish()))
+    }
+
+    /// Returns true if the canister was built from the same inputs by a previous build,
+    /// whose output still exists.
+    fn is_fresh(
+        &self,
+        build_cache: &BuildCache,
+        canister: &Canister,
+        fingerprint: &Option<String>,
+    ) -> bool {
+        match fingerprint {
+            Some(fingerprint) => {
+                build_cache.is_fresh(canister.get_name(), fingerprint)
+                    && canister.info.get_build_wasm_path().is_file()
+                    && canister.info.get_build_idl_path().is_file()
+            }
+            None => false,
+        }
+    }
+
     fn step_postbuild(
         &self,
         build_config: &BuildConfig,
         canister: &Canister,
         build_output: &BuildOutput,
+    ) -> DfxResult<()> {
+        self.step_copy_build_output(canister, build_output)?;
+        self.step_process_wasm(build_config, canister)?;
+        self.step_export_build_output(build_config, canister)?;
+        canister.postbuild(self, build_config)
+    }
+
+    fn step_copy_build_output(
+        &self,
+        canister: &Canister,
+        build_output: &BuildOutput,
     ) -> DfxResult<()> {
         // Copy the WASM and IDL files to canisters/NAME/...
         let IdlBuildOutput::File(build_idl_path) = &build_output.idl;
@@ -273,26 +389,127 @@ impl CanisterPool {
             std::fs::set_permissions(&wasm_file_path, perms)?;
         }
 
-        // And then create an canisters/IDL folder with canister DID files per canister ID.
+        Ok(())
+    }
+
+    /// Strip the Wasm module of a canister as the build profile says, embed its metadata,
+    /// report its size, and check that it is not too large.
+    fn step_process_wasm(&self, build_config: &BuildConfig, canister: &Canister) -> DfxResult {
+        let wasm_path = canister.info.get_build_wasm_path();
+        let mut module = std::fs::read(&wasm_path)?;
+        if !wasm::is_wasm(&module) {
+            // E.g. a compressed module, which is installed as is.
+            return Ok(());
+        }
+        if build_config.strip_wasm {
+            module = wasm::strip_custom_sections(&module, &build_config.keep_sections)?;
+        }
+        for (name, content) in build_metadata(&canister.info, build_config.git_revision.as_deref())?
+        {
+            module = wasm::set_custom_section(&module, &public_section_name(name), &content)?;
+        }
+        std::fs::write(&wasm_path, &module)?;
+
+        let prefix = build_config.get_output_prefix(&canister.info);
+        let report = wasm::size_report(&module)?;
+        info!(
+            self.logger,
+            "{}Wasm module of canister '{}': {} bytes, {} functions.",
+            prefix,
+            canister.get_name(),
+            report.size,
+            report.function_count
+        );
+        for (section, size) in &report.sections {
+            info!(
+                self.logger,
+                "{}  {:<24} {:>10} bytes", prefix, section, size
+            );
+        }
+
+        match build_config.max_wasm_size {
+            Some(max_size) if report.size as u64 > max_size => Err(anyhow!(
+                "The Wasm module of canister '{}' is {} bytes, more than the maximum of {} bytes.",
+                canister.get_name(),
+                report.size,
+                max_size
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Create an canisters/IDL folder with canister DID files per canister ID.
+    fn copy_idl_to_idl_root(&self, build_config: &BuildConfig, canister: &Canister) -> DfxResult {
         let idl_root = &build_config.idl_root;
         let canister_id = canister.canister_id();
         let idl_file_path = idl_root.join(canister_id.to_text()).with_extension("did");
 
         std::fs::create_dir_all(idl_file_path.parent().unwrap())?;
-        std::fs::copy(&build_idl_path, &idl_file_path)
+        std::fs::copy(canister.info.get_build_idl_path(), &idl_file_path)
             .map(|_| {})
-            .map_err(DfxError::from)?;
+            .map_err(DfxError::from)
+    }
+
+    /// Use the output of the last build of a canister that is not built this time, so that
+    /// the canisters depending on it can be built.
+    fn step_reuse_build_output(
+        &self,
+        build_config: &BuildConfig,
+        canister: &Canister,
+    ) -> DfxResult {
+        canister.reuse_build_output();
+        self.copy_idl_to_idl_root(build_config, canister)
+    }
+
+    /// Make the build output of a canister available to the other canisters and to the
+    /// output directory of the build profile.
+    fn step_export_build_output(
+        &self,
+        build_config: &BuildConfig,
+        canister: &Canister,
+    ) -> DfxResult<()> {
+        self.copy_idl_to_idl_root(build_config, canister)?;
 
         build_canister_js(&canister.canister_id(), &canister.info)?;
 
-        canister.postbuild(self, build_config)
+        // Copy the WASM and IDL files to the output directory of the build profile.
+        if let Some(output_root) = &build_config.output_root {
+            let output_path = output_root.join(canister.get_name());
+            std::fs::create_dir_all(&output_path)?;
+            let name = canister.get_name();
+            std::fs::copy(
+                canister.info.get_build_wasm_path(),
+                output_path.join(format!("{}.wasm", name)),
+            )?;
+            std::fs::copy(
+                canister.info.get_build_idl_path(),
+                output_path.join(format!("{}.did", name)),
+            )?;
+        }
+
+        Ok(())
     }
 
     fn step_postbuild_all(
         &self,
         build_config: &BuildConfig,
-        _order: &[CanisterId],
+        order: &[CanisterId],
     ) -> DfxResult<()> {
+        // Describe what the build produced. Canister IDs are not real when checking.
+        if !build_config.build_mode_check {
+            // Only some of the canisters may have been built, so update the last manifest.
+            let mut manifest = BuildManifest::load_or_default(&build_config.build_root)?;
+            manifest.retain(&build_config.canister_names);
+            for canister_id in order {
+                if let Some(canister) = self.get_canister(canister_id) {
+                    if canister.get_build_output().is_some() {
+                        manifest.add(&canister.info, &build_config.profile_name)?;
+                    }
+                }
+            }
+            manifest.save(&build_config.build_root)?;
+        }
+
         // We don't want to simply remove the whole directory, as in the future,
         // we may want to keep the IDL files downloaded from network.
         for canister in &self.canisters {
@@ -307,59 +524,262 @@ impl CanisterPool {
         Ok(())
     }
 
+    /// Build a single canister, unless the build cache shows that it is up to date. Returns
+    /// the result of the build and the fingerprint to record in the build cache, if any.
+    fn build_canister(
+        &self,
+        build_config: &BuildConfig,
+        build_cache: &BuildCache,
+        canister: &Canister,
+    ) -> (Result<Arc<BuildOutput>, BuildError>, Option<String>) {
+        let canister_id = canister.canister_id();
+        let name = canister.get_name();
+        let format = build_config.message_format;
+        emit(format, &BuildEvent::CanisterStarted { canister: name });
+        // The build cache is not used when checking the build, as canister IDs are random.
+        // The inputs are recorded as they are before the build, so that a change made while
+        // building is seen by the next build.
+        let fingerprint = if !build_config.build_mode_check {
+            // If the inputs cannot be found, the build will report why.
+            self.fingerprint(build_config, canister).unwrap_or(None)
+        } else {
+            None
+        };
+        if !build_config.force && self.is_fresh(build_cache, canister, &fingerprint) {
+            info!(
+                self.logger,
+                "Canister '{}' is up to date.",
+                canister.get_name()
+            );
+            let output = canister.reuse_build_output();
+            let result = self
+                .step_copy_build_output(canister, &output)
+                .and_then(|_| self.step_export_build_output(build_config, canister))
+                .map_err(|e| BuildError::PostBuildStepFailed(canister_id, Box::new(e)))
+                .map(|_| output);
+            emit(
+                format,
+                &BuildEvent::CanisterFinished {
+                    canister: name,
+                    success: result.is_ok(),
+                    fresh: true,
+                    error: result.as_ref().err().map(|e| e.to_string()),
+                },
+            );
+            return (result, fingerprint);
+        }
+
+        let build_result = self
+            .step_prebuild(build_config, canister)
+            .map_err(|e| BuildError::PreBuildStepFailed(canister_id.clone(), Box::new(e)))
+            .and_then(|_| {
+                self.step_build(build_config, canister)
+                    .map_err(|e| BuildError::BuildStepFailed(canister_id.clone(), Box::new(e)))
+            })
+            .and_then(|o| {
+                self.step_postbuild(build_config, canister, &o)
+                    .map_err(|e| BuildError::PostBuildStepFailed(canister_id.clone(), Box::new(e)))
+                    .map(|_| o)
+            });
+
+        // The builders report the diagnostics of successful builds.
+        if let Err(BuildError::BuildStepFailed(_, error)) = &build_result {
+            if let Some(stderr) = tool_stderr(error) {
+                emit_diagnostics(format, name, stderr);
+            }
+        }
+        emit(
+            format,
+            &BuildEvent::CanisterFinished {
+                canister: name,
+                success: build_result.is_ok(),
+                fresh: false,
+                error: build_result.as_ref().err().map(|e| e.to_string()),
+            },
+        );
+
+        let fingerprint = match &build_result {
+            Ok(_) => fingerprint,
+            Err(_) => None,
+        };
+        (build_result, fingerprint)
+    }
+
+    /// Build the canisters of the dependency graph on `build_config.jobs` threads. A
+    /// canister is built once all of its dependencies are, whether they succeeded or not.
+    /// If a selection is given, the other canisters reuse the output of their last build.
+    /// Returns the result of each build and the fingerprint to record for it, by node.
+    #[allow(clippy::type_complexity)]
+    fn build_graph(
+        &self,
+        build_config: &BuildConfig,
+        build_cache: &BuildCache,
+        graph: &DiGraph<CanisterId, ()>,
+        selection: Option<&BTreeSet<CanisterId>>,
+    ) -> DfxResult<BTreeMap<NodeIndex, (Result<Arc<BuildOutput>, BuildError>, Option<String>)>>
+    {
+        let (job_sender, job_receiver) = crossbeam::channel::unbounded::<NodeIndex>();
+        let (result_sender, result_receiver) = crossbeam::channel::unbounded();
+
+        // The number of dependencies of each canister that are not built yet.
+        let mut pending: BTreeMap<NodeIndex, usize> = graph
+            .node_indices()
+            .map(|node| (node, graph.neighbors_directed(node, Outgoing).count()))
+            .collect();
+        let mut results = BTreeMap::new();
+
+        crossbeam::scope(|s| {
+            for _ in 0..build_config.jobs.max(1) {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                s.spawn(move |_| {
+                    for node in job_receiver.iter() {
+                        let canister_id = graph.node_weight(node).unwrap().clone();
+                        let canister = match self.get_canister(&canister_id) {
+                            Some(canister) => canister,
+                            None => {
+                                let _ = result_sender.send((node, None));
+                                continue;
+                            }
+                        };
+                        if selection.map_or(false, |s| !s.contains(&canister_id)) {
+                            // Errors surface in the build of the canisters depending on it.
+                            let _ = self.step_reuse_build_output(build_config, canister);
+                            let _ = result_sender.send((node, None));
+                            continue;
+                        }
+                        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                            self.build_canister(build_config, build_cache, canister)
+                        }))
+                        .unwrap_or_else(|_| {
+                            let error = anyhow!("The build panicked.");
+                            (
+                                Err(BuildError::BuildStepFailed(canister_id, Box::new(error))),
+                                None,
+                            )
+                        });
+                        let _ = result_sender.send((node, Some(result)));
+                    }
+                });
+            }
+            // Only the workers hold the channels from now on.
+            drop(job_receiver);
+            drop(result_sender);
+
+            for (node, _) in pending.iter().filter(|(_, count)| **count == 0) {
+                let _ = job_sender.send(*node);
+            }
+            let mut remaining = pending.len();
+            while remaining > 0 {
+                let (node, result) = match result_receiver.recv() {
+                    Ok(message) => message,
+                    Err(_) => break,
+                };
+                remaining -= 1;
+                if let Some(result) = result {
+                    results.insert(node, result);
+                }
+                for dependent in graph.neighbors_directed(node, Incoming) {
+                    let count = pending.get_mut(&dependent).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        let _ = job_sender.send(dependent);
+                    }
+                }
+            }
+            // Let the workers stop.
+            drop(job_sender);
+        })
+        .map_err(|_| anyhow!("A build thread panicked."))?;
+
+        Ok(results)
+    }
+
     /// Build all canisters, returning a vector of results of each builds.
     pub fn build(
         &self,
         build_config: BuildConfig,
-    ) -> DfxResult<Vec<Result<&BuildOutput, BuildError>>> {
+    ) -> DfxResult<Vec<Result<Arc<BuildOutput>, BuildError>>> {
+        self.build_selected(build_config, None)
+    }
+
+    /// Build the canisters that changed and all the canisters depending on them, returning
+    /// a vector of results of each builds.
+    pub fn build_changed(
+        &self,
+        build_config: BuildConfig,
+        changed: &BTreeSet<CanisterId>,
+    ) -> DfxResult<Vec<Result<Arc<BuildOutput>, BuildError>>> {
+        self.build_selected(build_config, Some(changed))
+    }
+
+    /// Returns the canisters and, transitively, the canisters depending on them.
+    fn with_dependents(
+        graph: &DiGraph<CanisterId, ()>,
+        canisters: &BTreeSet<CanisterId>,
+    ) -> BTreeSet<CanisterId> {
+        let mut stack: Vec<NodeIndex> = graph
+            .node_indices()
+            .filter(|node| canisters.contains(&graph[*node]))
+            .collect();
+        let mut result = BTreeSet::new();
+        while let Some(node) = stack.pop() {
+            if result.insert(graph[node].clone()) {
+                stack.extend(graph.neighbors_directed(node, Incoming));
+            }
+        }
+        result
+    }
+
+    fn build_selected(
+        &self,
+        build_config: BuildConfig,
+        changed: Option<&BTreeSet<CanisterId>>,
+    ) -> DfxResult<Vec<Result<Arc<BuildOutput>, BuildError>>> {
+        self.clear_builder_caches();
         self.step_prebuild_all(&build_config)
             .map_err(|e| DfxError::new(BuildError::PreBuildAllStepFailed(Box::new(e))))?;
 
-        let graph = self.build_dependencies_graph()?;
-        let nodes = petgraph::algo::toposort(&graph, None).map_err(|cycle| {
-            let message = match graph.node_weight(cycle.node_id()) {
-                Some(canister_id) => match self.get_canister_info(canister_id) {
-                    Some(info) => info.get_name().to_string(),
-                    None => format!("<{}>", canister_id.to_text()),
-                },
-                None => "<Unknown>".to_string(),
-            };
-            BuildError::DependencyError(format!("Found circular dependency: {}", message))
-        })?;
+        let (graph, nodes) = self.build_dependencies_graph()?;
         let order: Vec<CanisterId> = nodes
             .iter()
             .rev() // Reverse the order, as we have a dependency graph, we want to reverse indices.
             .map(|idx| graph.node_weight(*idx).unwrap().clone())
             .collect();
 
+        let mut build_cache = BuildCache::load(&build_config.build_root);
+        let selection = changed.map(|changed| Self::with_dependents(&graph, changed));
+        let mut results =
+            self.build_graph(&build_config, &build_cache, &graph, selection.as_ref())?;
+
+        // Report the results in the order of the dependencies, whatever order the builds
+        // finished in.
         let mut result = Vec::new();
-        for canister_id in &order {
-            if let Some(canister) = self.get_canister(canister_id) {
-                result.push(
-                    self.step_prebuild(&build_config, canister)
-                        .map_err(|e| {
-                            BuildError::PreBuildStepFailed(canister_id.clone(), Box::new(e))
-                        })
-                        .and_then(|_| {
-                            self.step_build(&build_config, canister).map_err(|e| {
-                                BuildError::BuildStepFailed(canister_id.clone(), Box::new(e))
-                            })
-                        })
-                        .and_then(|o| {
-                            self.step_postbuild(&build_config, canister, o)
-                                .map_err(|e| {
-                                    BuildError::PostBuildStepFailed(
-                                        canister_id.clone(),
-                                        Box::new(e),
-                                    )
-                                })
-                                .map(|_| o)
-                        }),
-                );
+        for node in nodes.iter().rev() {
+            if let Some((build_result, fingerprint)) = results.remove(node) {
+                if !build_config.build_mode_check {
+                    let canister_id = graph.node_weight(*node).unwrap();
+                    let name = self.get_canister_info(canister_id).unwrap().get_name();
+                    match fingerprint {
+                        Some(fingerprint) => build_cache.insert(name, fingerprint),
+                        None => build_cache.remove(name),
+                    }
+                }
+                result.push(build_result);
             }
         }
+        if !build_config.build_mode_check {
+            build_cache.save()?;
+        }
 
-        self.step_postbuild_all(&build_config, &order)
+        let postbuild_all = self.step_postbuild_all(&build_config, &order);
+        emit(
+            build_config.message_format,
+            &BuildEvent::BuildFinished {
+                success: postbuild_all.is_ok() && result.iter().all(Result::is_ok),
+            },
+        );
+        postbuild_all
             .map_err(|e| DfxError::new(BuildError::PostBuildAllStepFailed(Box::new(e))))?;
 
         Ok(result)
@@ -8,6 +8,7 @@ use crate::lib::identity::identity_utils::CallSender;
 use crate::lib::models::canister::CanisterPool;
 use crate::lib::models::canister_id_store::CanisterIdStore;
 use crate::lib::operations::canister::{create_canister, install_canister};
+use crate::lib::watch::watch_canisters;
 use crate::util::{blob_from_arguments, get_candid_init_type};
 
 use anyhow::{anyhow, bail};
@@ -20,7 +21,9 @@ use ic_utils::interfaces::management_canister::builders::InstallMode;
 use slog::info;
 use std::convert::TryFrom;
 use std::time::Duration;
+use tokio::runtime::Runtime;
 
+#[allow(clippy::too_many_arguments)]
 pub async fn deploy_canisters(
     env: &dyn Environment,
     some_canister: Option<&str>,
@@ -28,6 +31,10 @@ pub async fn deploy_canisters(
     argument_type: Option<&str>,
     timeout: Duration,
     with_cycles: Option<&str>,
+    profile: Option<&str>,
+    force: bool,
+    jobs: usize,
+    allow_breaking: bool,
     call_sender: &CallSender,
 ) -> DfxResult {
     let log = env.get_logger();
@@ -37,6 +44,9 @@ pub async fn deploy_canisters(
         .ok_or_else(|| anyhow!("Cannot find dfx configuration file in the current working directory. Did you forget to create one?"))?;
     let initial_canister_id_store = CanisterIdStore::for_env(env)?;
 
+    // Check the build profile before creating any canister.
+    config.get_config().get_profile(profile)?;
+
     let canister_names = canisters_to_deploy(&config, some_canister)?;
     if some_canister.is_some() {
         info!(log, "Deploying: {}", canister_names.join(" "));
@@ -55,7 +65,7 @@ pub async fn deploy_canisters(
     )
     .await?;
 
-    build_canisters(env, &canister_names, &config)?;
+    build_canisters(env, &canister_names, &config, profile, force, jobs)?;
 
     install_canisters(
         env,
@@ -65,6 +75,7 @@ pub async fn deploy_canisters(
         argument,
         argument_type,
         timeout,
+        allow_breaking,
         call_sender,
     )
     .await?;
@@ -74,6 +85,51 @@ pub async fn deploy_canisters(
     Ok(())
 }
 
+/// Watch the canisters deployed by `deploy_canisters`, and rebuild and upgrade the ones
+/// whose source files change, until interrupted.
+#[allow(clippy::too_many_arguments)]
+pub fn watch_deployed_canisters(
+    env: &dyn Environment,
+    runtime: &Runtime,
+    some_canister: Option<&str>,
+    argument: Option<&str>,
+    argument_type: Option<&str>,
+    timeout: Duration,
+    profile: Option<&str>,
+    jobs: usize,
+    allow_breaking: bool,
+    call_sender: &CallSender,
+) -> DfxResult {
+    let config = env
+        .get_config()
+        .ok_or_else(|| anyhow!("Cannot find dfx configuration file in the current working directory. Did you forget to create one?"))?;
+    let canister_names = canisters_to_deploy(&config, some_canister)?;
+    let build_mode_check = false;
+    let canister_pool = CanisterPool::load(env, build_mode_check, &canister_names)?;
+    let build_config = BuildConfig::from_config(&config, profile)?.with_jobs(jobs);
+
+    watch_canisters(
+        env.get_logger(),
+        &canister_pool,
+        build_config,
+        |built_canisters| {
+            // Every canister exists by now, so they are all upgraded.
+            let canister_id_store = CanisterIdStore::for_env(env)?;
+            runtime.block_on(install_canisters(
+                env,
+                built_canisters,
+                &canister_id_store,
+                &config,
+                argument,
+                argument_type,
+                timeout,
+                allow_breaking,
+                call_sender,
+            ))
+        },
+    )
+}
+
 fn canisters_to_deploy(config: &Config, some_canister: Option<&str>) -> DfxResult<Vec<String>> {
     let mut canister_names = config
         .get_config()
@@ -149,12 +205,23 @@ async fn register_canisters(
     Ok(())
 }
 
-fn build_canisters(env: &dyn Environment, canister_names: &[String], config: &Config) -> DfxResult {
+fn build_canisters(
+    env: &dyn Environment,
+    canister_names: &[String],
+    config: &Config,
+    profile: Option<&str>,
+    force: bool,
+    jobs: usize,
+) -> DfxResult {
     info!(env.get_logger(), "Building canisters...");
     let build_mode_check = false;
     let canister_pool = CanisterPool::load(env, build_mode_check, &canister_names)?;
 
-    canister_pool.build_or_fail(BuildConfig::from_config(&config)?)
+    canister_pool.build_or_fail(
+        BuildConfig::from_config(&config, profile)?
+            .with_force(force)
+            .with_jobs(jobs),
+    )
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -166,6 +233,7 @@ async fn install_canisters(
     argument: Option<&str>,
     argument_type: Option<&str>,
     timeout: Duration,
+    allow_breaking: bool,
     call_sender: &CallSender,
 ) -> DfxResult {
     info!(env.get_logger(), "Installing canisters...");
@@ -210,6 +278,7 @@ async fn install_canisters(
             install_mode,
             timeout,
             &call_sender,
+            allow_breaking,
         )
         .await?;
     }
@@ -1,3 +1,6 @@
+use crate::lib::builders::supports_stable_types;
+use crate::lib::candid_compat::breaking_changes;
+use crate::lib::canister_info::motoko::MotokoCanisterInfo;
 use crate::lib::canister_info::CanisterInfo;
 use crate::lib::environment::Environment;
 use crate::lib::error::DfxResult;
@@ -7,13 +10,13 @@ use crate::lib::installers::assets::post_install_store_assets;
 use crate::lib::named_canister;
 use crate::lib::waiter::waiter_with_timeout;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use ic_agent::Agent;
 use ic_utils::call::AsyncCall;
 use ic_utils::interfaces::management_canister::builders::{CanisterInstall, InstallMode};
 use ic_utils::interfaces::ManagementCanister;
 use ic_utils::Canister;
-use slog::info;
+use slog::{info, warn};
 use std::time::Duration;
 
 #[allow(clippy::too_many_arguments)]
@@ -25,6 +28,7 @@ pub async fn install_canister(
     mode: InstallMode,
     timeout: Duration,
     call_sender: &CallSender,
+    allow_breaking: bool,
 ) -> DfxResult {
     let network = env.get_network_descriptor().unwrap();
     if !network.is_ic && named_canister::get_ui_canister_id(&network).is_none() {
@@ -52,9 +56,15 @@ pub async fn install_canister(
         canister_id,
     );
 
-    let wasm_path = canister_info
-        .get_output_wasm_path()
-        .expect("Cannot get WASM output path.");
+    if matches!(mode, InstallMode::Upgrade) {
+        check_upgrade_compatibility(env, canister_info, allow_breaking)?;
+        if canister_info.get_type() == "motoko" {
+            check_stable_compatibility(env, canister_info)?;
+        }
+    }
+
+    // Install the module built in the build root, which was post-processed by the build.
+    let wasm_path = canister_info.get_build_wasm_path();
     let wasm_module = std::fs::read(wasm_path)?;
 
     match call_sender {
@@ -86,6 +96,8 @@ pub async fn install_canister(
         }
     }
 
+    record_installed_interfaces(canister_info)?;
+
     if canister_info.get_type() == "assets" {
         match call_sender {
             CallSender::Wallet(wallet_id) | CallSender::SelectedIdWallet(wallet_id) => {
@@ -113,8 +125,91 @@ pub async fn install_canister(
         };
 
         info!(log, "Uploading assets to asset canister...");
-        post_install_store_assets(&canister_info, &agent, timeout).await?;
+        post_install_store_assets(env, &canister_info, &agent, timeout).await?;
     }
 
     Ok(())
 }
+
+/// Check that the interface of the built canister does not break the clients of the
+/// installed one, as dfx last installed it on this network.
+fn check_upgrade_compatibility(
+    env: &dyn Environment,
+    canister_info: &CanisterInfo,
+    allow_breaking: bool,
+) -> DfxResult {
+    let new_candid = match std::fs::read_to_string(canister_info.get_build_idl_path()) {
+        Ok(candid) => candid,
+        Err(_) => return Ok(()),
+    };
+    let old_candid = match std::fs::read_to_string(canister_info.get_deployed_idl_path()) {
+        Ok(candid) => candid,
+        Err(_) => return Ok(()),
+    };
+
+    let changes = breaking_changes(&old_candid, &new_candid)?;
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let message = format!(
+        "The upgrade of canister {} breaks the clients of its interface:\n{}",
+        canister_info.get_name(),
+        changes.join("\n")
+    );
+    if allow_breaking {
+        warn!(env.get_logger(), "{}", message);
+        Ok(())
+    } else {
+        bail!("{}\nUse --allow-breaking to upgrade anyway.", message)
+    }
+}
+
+/// Check that the stable variables of the new module of a Motoko canister can be
+/// initialized from those of the installed module, as kept by the last install.
+fn check_stable_compatibility(env: &dyn Environment, canister_info: &CanisterInfo) -> DfxResult {
+    let motoko_info = canister_info.as_info::<MotokoCanisterInfo>()?;
+    let deployed_path = motoko_info.get_deployed_stable_types_path();
+    let new_path = motoko_info.get_output_stable_types_path();
+    if !deployed_path.exists() || !new_path.exists() {
+        return Ok(());
+    }
+    if !supports_stable_types(env.get_cache().as_ref())? {
+        return Ok(());
+    }
+
+    let output = env
+        .get_cache()
+        .get_binary_command("moc")?
+        .arg("--stable-compatible")
+        .arg(&deployed_path)
+        .arg(new_path)
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "The stable variables of canister {} are not compatible with those of the installed module, so upgrading would lose its state:\n{}{}\nUse --mode reinstall to discard the state of the canister.",
+            canister_info.get_name(),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+/// Keep the interfaces of the installed module, to check the next upgrade against them.
+fn record_installed_interfaces(canister_info: &CanisterInfo) -> DfxResult {
+    let idl_path = canister_info.get_build_idl_path();
+    if idl_path.exists() {
+        std::fs::copy(&idl_path, canister_info.get_deployed_idl_path())?;
+    }
+    if canister_info.get_type() == "motoko" {
+        let motoko_info = canister_info.as_info::<MotokoCanisterInfo>()?;
+        let stable_types_path = motoko_info.get_output_stable_types_path();
+        if stable_types_path.exists() {
+            std::fs::copy(
+                stable_types_path,
+                motoko_info.get_deployed_stable_types_path(),
+            )?;
+        }
+    }
+    Ok(())
+}
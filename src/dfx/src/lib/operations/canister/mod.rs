@@ -3,7 +3,7 @@ mod deploy_canisters;
 mod install_canister;
 
 pub use create_canister::create_canister;
-pub use deploy_canisters::deploy_canisters;
+pub use deploy_canisters::{deploy_canisters, watch_deployed_canisters};
 pub use install_canister::install_canister;
 
 use crate::lib::canister_info::CanisterInfo;
@@ -1,4 +1,4 @@
-use indicatif::{ProgressBar as IndicatifProgressBar, ProgressDrawTarget};
+use indicatif::{ProgressBar as IndicatifProgressBar, ProgressDrawTarget, ProgressStyle};
 
 pub struct ProgressBar {
     bar: Option<IndicatifProgressBar>,
@@ -35,7 +35,26 @@ impl ProgressBar {
         }
     }
 
+    /// A progress bar of bytes, whose length is set once known.
+    pub fn new_progress(message: &str) -> Self {
+        let progress_bar = IndicatifProgressBar::new(0);
+        progress_bar.set_draw_target(ProgressDrawTarget::stderr());
+        progress_bar.set_style(
+            ProgressStyle::default_bar()
+                .template("{msg} [{bar:40}] {bytes}/{total_bytes} ({eta})")
+                .progress_chars("=> "),
+        );
+
+        progress_bar.set_message(message);
+
+        ProgressBar {
+            bar: Some(progress_bar),
+        }
+    }
+
     forward_fn_impl!(finish_with_message, message: &str);
+    forward_fn_impl!(set_length, length: u64);
+    forward_fn_impl!(inc, delta: u64);
 
     pub fn discard() -> Self {
         ProgressBar { bar: None }
@@ -43,6 +43,7 @@ pub fn get_network_descriptor<'a>(
     })?;
     let config = config.as_ref().get_config();
     let network_name = get_network_context()?;
+    config.check_resolved(&format!("networks.{}", network_name))?;
     match config.get_network(&network_name) {
         Some(ConfigNetwork::ConfigNetworkProvider(network_provider)) => {
             let provider_urls = match &network_provider.providers {
@@ -0,0 +1,268 @@
+//! Inspection and post-processing of the Wasm modules of canisters.
+use crate::lib::error::DfxResult;
+
+use anyhow::anyhow;
+use wasmparser::{BinaryReaderError, ModuleReader, SectionCode};
+
+/// The magic number and version that start every Wasm module.
+const WASM_HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// The prefixes of the custom sections holding the metadata of a canister, which are kept
+/// when stripping a module.
+const METADATA_SECTION_PREFIXES: &[&str] = &["icp:public ", "icp:private "];
+
+/// A section of a Wasm module.
+pub struct WasmSection<'a> {
+    /// The kind of the section (e.g. `code`), or `custom <name>` for custom sections.
+    pub name: String,
+    /// The name of the section, if it is a custom section.
+    pub custom_name: Option<String>,
+    /// The content of the section after its name, if it is a custom section.
+    pub custom_content: &'a [u8],
+    /// The number of functions defined by the section, for a function section.
+    pub function_count: u32,
+    /// The bytes of the section, including its id and size.
+    pub bytes: &'a [u8],
+}
+
+/// The size of a Wasm module, broken down by section.
+pub struct WasmSizeReport {
+    pub size: usize,
+    pub function_count: u32,
+    pub sections: Vec<(String, usize)>,
+}
+
+fn wasm_error(error: BinaryReaderError) -> anyhow::Error {
+    anyhow!(
+        "Invalid Wasm module: {} (at offset {}).",
+        error.message,
+        error.offset
+    )
+}
+
+/// Returns true if the content starts like a Wasm module, and not e.g. a compressed one.
+pub fn is_wasm(module: &[u8]) -> bool {
+    module.starts_with(WASM_HEADER)
+}
+
+/// Split a Wasm module into its sections, in order.
+pub fn read_sections(module: &[u8]) -> DfxResult<Vec<WasmSection<'_>>> {
+    let mut reader = ModuleReader::new(module).map_err(wasm_error)?;
+    let mut sections = vec![];
+    while !reader.eof() {
+        let start = reader.current_position();
+        let section = reader.read().map_err(wasm_error)?;
+        let end = reader.current_position();
+        let bytes = &module[start..end];
+
+        let (name, custom_name, custom_content) = match section.code {
+            SectionCode::Custom { name, .. } => {
+                // Skip the id, the size and the name of the section.
+                let (_, size_length) = read_u32_leb128(&bytes[1..]);
+                let (name_length, name_length_length) = read_u32_leb128(&bytes[1 + size_length..]);
+                let content_start = 1 + size_length + name_length_length + name_length as usize;
+                (
+                    format!("custom {}", name),
+                    Some(name.to_string()),
+                    &bytes[content_start..],
+                )
+            }
+            code => (format!("{:?}", code).to_lowercase(), None, &bytes[..0]),
+        };
+        let function_count = match section.code {
+            SectionCode::Function => section
+                .get_function_section_reader()
+                .map_err(wasm_error)?
+                .get_count(),
+            _ => 0,
+        };
+        sections.push(WasmSection {
+            name,
+            custom_name,
+            custom_content,
+            function_count,
+            bytes,
+        });
+    }
+    Ok(sections)
+}
+
+/// Remove the custom sections of a Wasm module, including the names of its functions,
+/// except for the metadata of the canister and the custom sections listed in `keep`.
+pub fn strip_custom_sections(module: &[u8], keep: &[String]) -> DfxResult<Vec<u8>> {
+    let mut stripped = WASM_HEADER.to_vec();
+    for section in read_sections(module)? {
+        let kept = match &section.custom_name {
+            Some(name) => {
+                keep.contains(name)
+                    || METADATA_SECTION_PREFIXES
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix))
+            }
+            None => true,
+        };
+        if kept {
+            stripped.extend_from_slice(section.bytes);
+        }
+    }
+    Ok(stripped)
+}
+
+/// Returns the content of a custom section of a Wasm module, if it has one with this name.
+pub fn get_custom_section(module: &[u8], name: &str) -> DfxResult<Option<Vec<u8>>> {
+    Ok(read_sections(module)?
+        .into_iter()
+        .find(|section| section.custom_name.as_deref() == Some(name))
+        .map(|section| section.custom_content.to_vec()))
+}
+
+/// Add a custom section at the end of a Wasm module, replacing the sections with the same
+/// name.
+pub fn set_custom_section(module: &[u8], name: &str, content: &[u8]) -> DfxResult<Vec<u8>> {
+    let mut result = WASM_HEADER.to_vec();
+    for section in read_sections(module)? {
+        if section.custom_name.as_deref() != Some(name) {
+            result.extend_from_slice(section.bytes);
+        }
+    }
+
+    let mut payload = vec![];
+    write_u32_leb128(&mut payload, name.len() as u32);
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(content);
+
+    result.push(0);
+    write_u32_leb128(&mut result, payload.len() as u32);
+    result.extend_from_slice(&payload);
+    Ok(result)
+}
+
+pub fn size_report(module: &[u8]) -> DfxResult<WasmSizeReport> {
+    let sections = read_sections(module)?;
+    Ok(WasmSizeReport {
+        size: module.len(),
+        function_count: sections.iter().map(|s| s.function_count).sum(),
+        sections: sections
+            .into_iter()
+            .map(|s| (s.name, s.bytes.len()))
+            .collect(),
+    })
+}
+
+fn write_u32_leb128(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Returns the value and the number of bytes of an unsigned LEB128 number. Only used on
+/// sections that were already validated by the parser.
+fn read_u32_leb128(bytes: &[u8]) -> (u32, usize) {
+    let mut value = 0;
+    for (i, byte) in bytes.iter().enumerate() {
+        value |= u32::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return (value, i + 1);
+        }
+    }
+    (value, bytes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TYPE_SECTION: &[u8] = &[0x01, 0x04, 0x01, 0x60, 0x00, 0x00];
+    const FUNCTION_SECTION: &[u8] = &[0x03, 0x02, 0x01, 0x00];
+    const CODE_SECTION: &[u8] = &[0x0a, 0x04, 0x01, 0x02, 0x00, 0x0b];
+    const NAME_SECTION: &[u8] = &[
+        0x00, 0x0b, 0x04, b'n', b'a', b'm', b'e', 0x01, 0x04, 0x01, 0x00, 0x01, b'f',
+    ];
+    const KEEP_SECTION: &[u8] = &[0x00, 0x06, 0x04, b'k', b'e', b'e', b'p', 0x2a];
+
+    fn module(sections: &[&[u8]]) -> Vec<u8> {
+        let mut module = WASM_HEADER.to_vec();
+        for section in sections {
+            module.extend_from_slice(section);
+        }
+        module
+    }
+
+    #[test]
+    fn strip_keeps_code_and_listed_sections() {
+        let original = module(&[
+            TYPE_SECTION,
+            FUNCTION_SECTION,
+            CODE_SECTION,
+            NAME_SECTION,
+            KEEP_SECTION,
+        ]);
+
+        let stripped = strip_custom_sections(&original, &["keep".to_string()]).unwrap();
+        assert_eq!(
+            stripped,
+            module(&[TYPE_SECTION, FUNCTION_SECTION, CODE_SECTION, KEEP_SECTION])
+        );
+
+        let stripped = strip_custom_sections(&original, &[]).unwrap();
+        assert_eq!(
+            stripped,
+            module(&[TYPE_SECTION, FUNCTION_SECTION, CODE_SECTION])
+        );
+    }
+
+    #[test]
+    fn custom_sections_are_replaced() {
+        let original = module(&[TYPE_SECTION, FUNCTION_SECTION, CODE_SECTION, KEEP_SECTION]);
+        assert_eq!(
+            get_custom_section(&original, "keep").unwrap(),
+            Some(vec![0x2a])
+        );
+
+        let content = vec![b'x'; 200];
+        let module = set_custom_section(&original, "icp:public test", &content).unwrap();
+        let module = set_custom_section(&module, "keep", b"kept").unwrap();
+        assert_eq!(
+            get_custom_section(&module, "icp:public test").unwrap(),
+            Some(content)
+        );
+        assert_eq!(
+            get_custom_section(&module, "keep").unwrap(),
+            Some(b"kept".to_vec())
+        );
+        assert_eq!(get_custom_section(&module, "other").unwrap(), None);
+
+        // Metadata is kept when stripping.
+        let stripped = strip_custom_sections(&module, &[]).unwrap();
+        assert!(get_custom_section(&stripped, "icp:public test")
+            .unwrap()
+            .is_some());
+        assert_eq!(get_custom_section(&stripped, "keep").unwrap(), None);
+    }
+
+    #[test]
+    fn size_report_lists_sections() {
+        let module = module(&[TYPE_SECTION, FUNCTION_SECTION, CODE_SECTION, NAME_SECTION]);
+        let report = size_report(&module).unwrap();
+
+        assert_eq!(report.size, module.len());
+        assert_eq!(report.function_count, 1);
+        assert_eq!(
+            report.sections,
+            vec![
+                ("type".to_string(), TYPE_SECTION.len()),
+                ("function".to_string(), FUNCTION_SECTION.len()),
+                ("code".to_string(), CODE_SECTION.len()),
+                ("custom name".to_string(), NAME_SECTION.len()),
+            ]
+        );
+        assert!(is_wasm(&module));
+        assert!(!is_wasm(b"\x1f\x8b\x08"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Rebuilding canisters when their inputs change, for `dfx build --watch` and
+//! `dfx deploy --watch`.
+use crate::lib::builders::BuildConfig;
+use crate::lib::error::DfxResult;
+use crate::lib::models::canister::CanisterPool;
+
+use ic_types::principal::Principal as CanisterId;
+use slog::{error, info, warn, Logger};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// How often the inputs are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long the inputs must stay unchanged before a build starts, so that saving several
+/// files at once only triggers one build.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The modification time and size of every file under the inputs of a canister. A missing
+/// input has no entry, so that creating or deleting a file is a change.
+type Stamps = BTreeMap<PathBuf, (SystemTime, u64)>;
+
+fn stamp_path(path: &Path, stamps: &mut Stamps) {
+    for entry in WalkDir::new(path).into_iter().filter_map(Result::ok) {
+        if let Ok(metadata) = entry.metadata() {
+            if metadata.is_file() {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                stamps.insert(entry.into_path(), (modified, metadata.len()));
+            }
+        }
+    }
+}
+
+/// Polls the inputs of the canisters of a pool.
+struct CanisterWatcher<'a> {
+    pool: &'a CanisterPool,
+    inputs: BTreeMap<CanisterId, Vec<PathBuf>>,
+    stamps: BTreeMap<CanisterId, Stamps>,
+}
+
+impl<'a> CanisterWatcher<'a> {
+    fn new(pool: &'a CanisterPool) -> DfxResult<Self> {
+        let mut inputs = BTreeMap::new();
+        for canister in pool.get_canister_list() {
+            match pool.get_canister_inputs(canister)? {
+                Some(paths) => {
+                    inputs.insert(canister.canister_id(), paths);
+                }
+                None => warn!(
+                    pool.get_logger(),
+                    "Cannot watch canister {} because its inputs are unknown. Declare them with \"inputs\" in dfx.json.",
+                    canister.get_name()
+                ),
+            }
+        }
+        let mut watcher = CanisterWatcher {
+            pool,
+            inputs,
+            stamps: BTreeMap::new(),
+        };
+        watcher.stamps = watcher.read_stamps();
+        Ok(watcher)
+    }
+
+    fn read_stamps(&self) -> BTreeMap<CanisterId, Stamps> {
+        self.inputs
+            .iter()
+            .map(|(canister_id, paths)| {
+                let mut stamps = Stamps::new();
+                for path in paths {
+                    stamp_path(path, &mut stamps);
+                }
+                (canister_id.clone(), stamps)
+            })
+            .collect()
+    }
+
+    /// Blocks until the inputs of some canisters changed and then stayed the same for a
+    /// moment. Returns these canisters.
+    fn wait_for_changes(&mut self) -> BTreeSet<CanisterId> {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let mut current = self.read_stamps();
+            if current == self.stamps {
+                continue;
+            }
+            loop {
+                std::thread::sleep(DEBOUNCE);
+                let next = self.read_stamps();
+                if next == current {
+                    break;
+                }
+                current = next;
+            }
+            let changed = current
+                .iter()
+                .filter(|(canister_id, stamps)| self.stamps.get(canister_id) != Some(stamps))
+                .map(|(canister_id, _)| canister_id.clone())
+                .collect();
+            self.stamps = current;
+            return changed;
+        }
+    }
+
+    /// Read the inputs again after a build, as the build can change them (e.g. with new
+    /// imports). The stamps of the inputs watched already are kept, so that edits made
+    /// during the build still trigger the next one. A canister whose inputs cannot be read
+    /// keeps its previous inputs.
+    fn refresh(&mut self) {
+        self.pool.clear_builder_caches();
+        for canister in self.pool.get_canister_list() {
+            let paths = match self.pool.get_canister_inputs(canister) {
+                Ok(Some(paths)) => paths,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!(
+                        self.pool.get_logger(),
+                        "Cannot read the inputs of canister {}: {:#}",
+                        canister.get_name(),
+                        e
+                    );
+                    continue;
+                }
+            };
+            let canister_id = canister.canister_id();
+            let previous = self
+                .inputs
+                .insert(canister_id.clone(), paths.clone())
+                .unwrap_or_default();
+            let stamps = self.stamps.entry(canister_id).or_default();
+            *stamps = std::mem::take(stamps)
+                .into_iter()
+                .filter(|(file, _)| paths.iter().any(|path| file.starts_with(path)))
+                .collect();
+            for path in paths.iter().filter(|path| !previous.contains(path)) {
+                stamp_path(path, stamps);
+            }
+        }
+    }
+}
+
+fn canister_names(pool: &CanisterPool, canister_ids: &BTreeSet<CanisterId>) -> Vec<String> {
+    canister_ids
+        .iter()
+        .filter_map(|canister_id| pool.get_canister_info(canister_id))
+        .map(|info| info.get_name().to_string())
+        .collect()
+}
+
+/// Watch the inputs of the canisters of the pool, and build the canisters whose inputs
+/// changed and the canisters depending on them. `after_build` is called with the names of
+/// the canisters that were built successfully. Build errors are logged, and never stop
+/// watching.
+pub fn watch_canisters<F>(
+    logger: &Logger,
+    pool: &CanisterPool,
+    build_config: BuildConfig,
+    mut after_build: F,
+) -> DfxResult
+where
+    F: FnMut(&[String]) -> DfxResult,
+{
+    let mut watcher = CanisterWatcher::new(pool)?;
+    info!(logger, "Watching for changes. Press Ctrl-C to stop.");
+    loop {
+        let changed = watcher.wait_for_changes();
+        if changed.is_empty() {
+            continue;
+        }
+        info!(
+            logger,
+            "Changes detected in {}. Rebuilding...",
+            canister_names(pool, &changed).join(", ")
+        );
+
+        let mut built = BTreeSet::new();
+        match pool.build_changed(build_config.clone(), &changed) {
+            Ok(results) => {
+                for result in results {
+                    match result {
+                        Ok(output) => {
+                            built.insert(output.canister_id.clone());
+                        }
+                        Err(e) => error!(logger, "{}", e),
+                    }
+                }
+            }
+            Err(e) => error!(logger, "{:#}", e),
+        }
+
+        let built = canister_names(pool, &built);
+        if !built.is_empty() {
+            if let Err(e) = after_build(&built) {
+                error!(logger, "{:#}", e);
+            }
+        }
+        watcher.refresh();
+        info!(logger, "Watching for changes. Press Ctrl-C to stop.");
+    }
+}
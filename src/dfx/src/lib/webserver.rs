@@ -3,7 +3,7 @@ use crate::lib::error::{DfxError, DfxResult};
 use crate::lib::locations::canister_did_location;
 use crate::lib::models::canister_id_store::CanisterIdStore;
 use crate::lib::network::network_descriptor::NetworkDescriptor;
-use crate::util::check_candid_file;
+use crate::util::check_candid;
 
 mod http_transport;
 
@@ -62,6 +62,10 @@ enum Format {
     Javascript,
     #[serde(rename = "ts")]
     Typescript,
+    #[serde(rename = "mo")]
+    Motoko,
+    #[serde(rename = "rs")]
+    Rust,
 }
 
 #[derive(Deserialize)]
@@ -94,17 +98,27 @@ async fn candid(
         .canonicalize()
         .map_err(|_e| anyhow!("Cannot find candid file."))
         .map_err(ErrorInternalServerError)?;
+    let name = candid_path.to_string_lossy().to_string();
+    let candid = std::fs::read_to_string(&candid_path).map_err(ErrorInternalServerError)?;
 
     let content = match info.format {
-        None => std::fs::read_to_string(candid_path).map_err(ErrorInternalServerError)?,
+        None => candid,
         Some(Format::Javascript) => {
-            let (env, ty) = check_candid_file(&candid_path).map_err(ErrorInternalServerError)?;
+            let (env, ty) = check_candid(&name, &candid).map_err(ErrorInternalServerError)?;
             candid::bindings::javascript::compile(&env, &ty)
         }
         Some(Format::Typescript) => {
-            let (env, ty) = check_candid_file(&candid_path).map_err(ErrorInternalServerError)?;
+            let (env, ty) = check_candid(&name, &candid).map_err(ErrorInternalServerError)?;
             candid::bindings::typescript::compile(&env, &ty)
         }
+        Some(Format::Motoko) => {
+            let (env, ty) = check_candid(&name, &candid).map_err(ErrorInternalServerError)?;
+            candid::bindings::motoko::compile(&env, &ty)
+        }
+        Some(Format::Rust) => {
+            let (env, ty) = check_candid(&name, &candid).map_err(ErrorInternalServerError)?;
+            candid::bindings::rust::compile(&env, &ty)
+        }
     };
     let response = HttpResponse::Ok().body(content);
     Ok(response)
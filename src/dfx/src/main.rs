@@ -1,3 +1,5 @@
+use crate::config::dfinity::format_diagnostics;
+use crate::config::migration::CURRENT_CONFIG_VERSION;
 use crate::config::{dfx_version, dfx_version_str};
 use crate::lib::environment::{Environment, EnvironmentImpl};
 use crate::lib::logger::{create_root_logger, LoggingMode};
@@ -100,6 +102,43 @@ fn setup_logging(opts: &CliOpts) -> (bool, slog::Logger) {
     (level >= 0, create_root_logger(level, mode))
 }
 
+/// Report the problems found while validating dfx.json, unless the user is already
+/// working with the configuration through `dfx config`.
+fn warn_on_config_diagnostics(env: &dyn Environment, command: &commands::Command) {
+    if is_warning_disabled("config_validation") {
+        return;
+    }
+    if let commands::Command::Config(_) = command {
+        return;
+    }
+    if let Some(config) = env.get_config() {
+        for line in format_diagnostics(config.get_path(), config.get_diagnostics()) {
+            slog::warn!(env.get_logger(), "{}", line);
+        }
+    }
+}
+
+/// Warn once per command, with the logger, when dfx.json uses an older configuration layout.
+fn warn_on_outdated_config(env: &dyn Environment, command: &commands::Command) {
+    if is_warning_disabled("config_version") {
+        return;
+    }
+    if let commands::Command::Config(_) = command {
+        return;
+    }
+    if let Some(config) = env.get_config() {
+        if let Some(version) = config.get_outdated_version() {
+            slog::warn!(
+                env.get_logger(),
+                "{} uses version {} of the configuration layout, but this version of dfx uses version {}. Run `dfx config migrate` to update it.",
+                config.get_path().display(),
+                version,
+                CURRENT_CONFIG_VERSION
+            );
+        }
+    }
+}
+
 fn main() {
     let cli_opts = CliOpts::parse();
     let (progress_bar, log) = setup_logging(&cli_opts);
@@ -118,6 +157,8 @@ fn main() {
                         env.get_logger(),
                         "Trace mode enabled. Lots of logs coming up."
                     );
+                    warn_on_config_diagnostics(&env, &command);
+                    warn_on_outdated_config(&env, &command);
                     witness_telemetry_consent().and_then(|()| commands::exec(&env, command))
                 }
                 Err(e) => Err(e),
@@ -44,6 +44,13 @@ pub fn cycle_amount_validator(cycles: &str) -> Result<(), String> {
     Err("Must be a non negative amount.".to_string())
 }
 
+pub fn jobs_validator(jobs: &str) -> Result<(), String> {
+    match jobs.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => Ok(()),
+        _ => Err("Must be a positive number of jobs.".to_string()),
+    }
+}
+
 pub fn compute_allocation_validator(compute_allocation: &str) -> Result<(), String> {
     if let Ok(num) = compute_allocation.parse::<u64>() {
         if num <= 100 {
@@ -0,0 +1,36 @@
+//! Glob patterns matching paths relative to a directory, like `src/**/*.py`.
+use crate::lib::error::DfxResult;
+
+use regex::Regex;
+
+/// Whether a path contains wildcards.
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(|c| c == '*' || c == '?')
+}
+
+/// Returns a regular expression matching the paths matched by a glob pattern, with `/` as
+/// separator. `*` and `?` match within a path component, and `**` matches any number of
+/// components.
+pub fn glob_to_regex(pattern: &str) -> DfxResult<Regex> {
+    let mut expr = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no directory at all.
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    expr.push_str("(.*/)?");
+                } else {
+                    expr.push_str(".*");
+                }
+            }
+            '*' => expr.push_str("[^/]*"),
+            '?' => expr.push_str("[^/]"),
+            c => expr.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expr.push('$');
+    Ok(Regex::new(&expr)?)
+}
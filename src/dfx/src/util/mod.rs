@@ -11,6 +11,7 @@ use std::time::Duration;
 
 pub mod assets;
 pub mod clap;
+pub mod glob;
 
 // The user can pass in port "0" to dfx start or dfx bootstrap i.e. "127.0.0.1:0" or "[::1]:0",
 // thus, we need to recreate SocketAddr with the kernel provided dynmically allocated port here.
@@ -98,7 +99,12 @@ pub fn get_candid_init_type(idl_path: &std::path::Path) -> Option<(TypeEnv, Func
 
 pub fn check_candid_file(idl_path: &std::path::Path) -> DfxResult<(TypeEnv, Option<Type>)> {
     let idl_file = std::fs::read_to_string(idl_path)?;
-    let ast = candid::pretty_parse::<IDLProg>(&idl_path.to_string_lossy(), &idl_file)?;
+    check_candid(&idl_path.to_string_lossy(), &idl_file)
+}
+
+/// Check a Candid interface. The name is used in error messages.
+pub fn check_candid(name: &str, candid: &str) -> DfxResult<(TypeEnv, Option<Type>)> {
+    let ast = candid::pretty_parse::<IDLProg>(name, candid)?;
     let mut env = TypeEnv::new();
     let actor = check_prog(&mut env, &ast)?;
     Ok((env, actor))